@@ -0,0 +1,539 @@
+/*!
+ * Configuration, which is read from the `.version.json` file
+ */
+
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+
+use crate::{MAJOR_BUMP, MINOR_BUMP, PATCH_BUMP};
+
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    pub helm: Option<serde_json::Value>,
+    pub npm: Option<serde_json::Value>,
+    pub composer: Option<serde_json::Value>,
+    pub cargo: Option<serde_json::Value>,
+    pub python: Option<serde_json::Value>,
+
+    /// Files of any format updated with a regular expression
+    #[serde(default)]
+    pub files: Vec<VersionFile>,
+
+    /// File (a path or an object with `path` and `key`) to read the current version from
+    /// instead of tags
+    pub version_source: Option<serde_json::Value>,
+
+    /// How the `version` of Helm charts changes along with `appVersion`
+    #[serde(default)]
+    pub helm_chart_version: ChartVersion,
+
+    /// Commit types in addition to (or replacing) the default ones
+    #[serde(default)]
+    pub types: Vec<CommitType>,
+
+    /// While version is `0.y.z`, breaking changes bump minor and features bump patch
+    #[serde(default)]
+    pub pre_major: bool,
+
+    /// Part of the tag before the version, e.g. `v` in `v1.2.3` or `app@` in `app@1.2.3`
+    #[serde(default = "default_tag_prefix")]
+    pub tag_prefix: String,
+
+    /// Consider tags on all branches, not only tags reachable from HEAD
+    #[serde(default)]
+    pub all_tags: bool,
+
+    /// Glob patterns of branches, which releases are allowed from
+    #[serde(default = "default_release_branches")]
+    pub release_branches: Vec<String>,
+
+    /// Kind of the release tag
+    #[serde(default)]
+    pub tag_type: TagType,
+
+    /// Message of annotated and signed tags with the same placeholders as `releaseCommitMessage`
+    #[serde(default = "default_tag_message")]
+    pub tag_message: String,
+
+    /// Message of the release commit. Supports `{version}`, `{major}`, `{minor}`, `{patch}`,
+    /// `{tag}`, `{previousVersion}`, `{previousTag}`, `{date}` and `{changelog}` placeholders.
+    #[serde(default = "default_release_commit_message")]
+    pub release_commit_message: String,
+
+    /// Lines appended to the release commit message, e.g. `[skip ci]`
+    #[serde(default)]
+    pub release_commit_trailers: Vec<String>,
+
+    /// Add `Signed-off-by` trailer with the committer identity, like `git commit --signoff`
+    #[serde(default)]
+    pub sign_off: bool,
+
+    /// Sign the release commit, same as with the `commit.gpgSign` git option
+    #[serde(default)]
+    pub sign_commits: bool,
+
+    /// Remote, which `--push` sends the release branch and tags to
+    #[serde(default = "default_remote")]
+    pub remote: String,
+
+    /// Environment variable with the token used to push over HTTPS, e.g. in CI
+    #[serde(default = "default_push_token_env")]
+    pub push_token_env: String,
+
+    /// Independently versioned packages of a monorepo
+    #[serde(default)]
+    pub packages: Vec<Package>,
+}
+
+/// Package of a monorepo with its own version, tags and changelog
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    /// Directory of the package relative to the repository root,
+    /// only commits changing files inside it affect the package version
+    pub path: String,
+
+    /// Defaults to the last component of the path followed by `/v`, e.g. `api/v`
+    pub tag_prefix: Option<String>,
+
+    /// Version files relative to the package path
+    pub helm: Option<serde_json::Value>,
+    pub npm: Option<serde_json::Value>,
+    pub composer: Option<serde_json::Value>,
+    pub cargo: Option<serde_json::Value>,
+    pub python: Option<serde_json::Value>,
+
+    #[serde(default)]
+    pub files: Vec<VersionFile>,
+
+    /// Relative to the package path
+    pub version_source: Option<serde_json::Value>,
+}
+
+/// File with a version found by a user-defined regular expression
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Clone)]
+pub struct VersionFile {
+    /// Path or array of paths
+    pub path: serde_json::Value,
+
+    /// Regular expression with the `version` capture group
+    #[serde(deserialize_with = "deserialize_version_regex")]
+    pub regex: String,
+
+    /// Replaces the whole match when set, otherwise only the `version` group is replaced.
+    /// Supports `{version}`, `{major}`, `{minor}`, `{patch}` and `{tag}` placeholders.
+    pub replacement: Option<String>,
+}
+
+/// Checks the regex while reading the config, so an invalid one is reported
+/// before anything is changed
+fn deserialize_version_regex<'de, D>(deserializer: D) -> Result<String, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    use serde::de::Error;
+    use serde::Deserialize;
+
+    let regex = String::deserialize(deserializer)?;
+    let compiled = regex::Regex::new(&regex).map_err(D::Error::custom)?;
+
+    if !compiled.capture_names().any(|name| name == Some("version")) {
+        return Err(D::Error::custom(format!(
+            "regex `{}` has no `version` capture group",
+            regex
+        )));
+    }
+
+    Ok(regex)
+}
+
+impl Package {
+    /// Package with default version files, which are skipped when not found
+    fn new(path: &str, tag_prefix: Option<String>) -> Package {
+        Package {
+            path: path.to_string(),
+            tag_prefix,
+            helm: Some(serde_json::Value::String(String::from(".helm/Chart.yaml"))),
+            npm: Some(serde_json::Value::String(String::from("package.json"))),
+            composer: Some(serde_json::Value::String(String::from("composer.json"))),
+            cargo: Some(serde_json::Value::String(String::from("Cargo.toml"))),
+            python: Some(default_python_files()),
+            files: Vec::new(),
+            version_source: None,
+        }
+    }
+
+    pub fn tag_prefix(&self) -> String {
+        self.tag_prefix.clone().unwrap_or_else(|| {
+            let name = Path::new(&self.path)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+
+            format!("{}/v", name)
+        })
+    }
+
+    /// Joins the package path with a path relative to the package,
+    /// keeping `!` of exclude patterns in front
+    pub fn join(&self, path: &str) -> String {
+        if self.path.is_empty() {
+            return path.to_string();
+        }
+
+        if let Some(exclude) = path.strip_prefix('!') {
+            return format!("!{}", self.join(exclude));
+        }
+
+        Path::new(&self.path)
+            .join(path)
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Same as `join`, but for version files from the config, which are a path,
+    /// an object with `path` or an array of them
+    pub fn join_paths(&self, paths: &Option<serde_json::Value>) -> serde_json::Value {
+        match paths {
+            Some(serde_json::Value::String(path)) => serde_json::Value::String(self.join(path)),
+            Some(serde_json::Value::Object(file)) => {
+                let mut file = file.clone();
+
+                if let Some(serde_json::Value::String(path)) = file.get("path") {
+                    let path = self.join(path);
+                    file.insert(String::from("path"), serde_json::Value::String(path));
+                }
+
+                serde_json::Value::Object(file)
+            }
+            Some(serde_json::Value::Array(many)) => serde_json::Value::Array(
+                many.iter()
+                    .map(|path| self.join_paths(&Some(path.clone())))
+                    .collect(),
+            ),
+            Some(other) => other.clone(),
+            None => serde_json::Value::Null,
+        }
+    }
+}
+
+/// `__version__` modules are not looked for by default, because their location differs
+fn default_python_files() -> serde_json::Value {
+    serde_json::json!(["pyproject.toml", "setup.cfg"])
+}
+
+fn default_release_branches() -> Vec<String> {
+    ["main", "master", "release/*", "*.x"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+fn default_remote() -> String {
+    String::from("origin")
+}
+
+fn default_push_token_env() -> String {
+    String::from("GIT_TOKEN")
+}
+
+fn default_tag_prefix() -> String {
+    String::from("v")
+}
+
+fn default_tag_message() -> String {
+    String::from("Release")
+}
+
+fn default_release_commit_message() -> String {
+    String::from("chore(release): {tag}")
+}
+
+/// Git object created for the release tag
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TagType {
+    /// Tag object with a tagger, a date and a message, signed when `tag.gpgSign` is set
+    #[default]
+    Annotated,
+    /// Plain reference to the release commit
+    Lightweight,
+    /// Annotated tag signed with the key configured by `user.signingKey` and `gpg.format`
+    Signed,
+}
+
+/// Change of the Helm chart `version` on release
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartVersion {
+    /// Only `appVersion` is changed
+    #[default]
+    None,
+    /// Chart version is the same as the released version
+    Lockstep,
+    /// Chart version is bumped from its own value by the same level as the release
+    Bump,
+}
+
+/// Version bump required by a commit type
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    #[default]
+    None,
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Bump {
+    /// Converts bump level to the bump flags used by `bump`
+    pub fn flags(self) -> u8 {
+        match self {
+            Bump::None => 0,
+            Bump::Patch => PATCH_BUMP,
+            Bump::Minor => MINOR_BUMP,
+            Bump::Major => MAJOR_BUMP,
+        }
+    }
+}
+
+/// Conventional commit type, e.g. `feat` or `fix`
+#[derive(serde_derive::Deserialize, Debug, PartialEq, Clone)]
+pub struct CommitType {
+    #[serde(rename = "type")]
+    pub name: String,
+
+    #[serde(default)]
+    pub bump: Bump,
+
+    /// Title of the changelog section, commits are not listed in changelog without it
+    pub section: Option<String>,
+}
+
+impl CommitType {
+    fn new(name: &str, bump: Bump, section: &str) -> CommitType {
+        CommitType {
+            name: name.to_string(),
+            bump,
+            section: Some(section.to_string()),
+        }
+    }
+}
+
+/// Commit types known without any configuration, in order of changelog sections
+pub fn default_types() -> Vec<CommitType> {
+    vec![
+        CommitType::new("feat", Bump::Minor, "Features"),
+        CommitType::new("fix", Bump::Patch, "Bug Fixes"),
+        CommitType::new("refactor", Bump::Patch, "Code Refactoring"),
+        // Usually changes in documentation does not affect
+        // main source code and not require version bump.
+        CommitType::new("docs", Bump::None, "Documentation"),
+        CommitType::new("chore", Bump::Patch, "Chores"),
+        CommitType::new("revert", Bump::None, "Reverts"),
+    ]
+}
+
+impl Default for Config {
+    /// Same as an empty `.version.json`
+    fn default() -> Config {
+        Config {
+            helm: None,
+            npm: None,
+            composer: None,
+            cargo: None,
+            python: None,
+            files: Vec::new(),
+            version_source: None,
+            helm_chart_version: ChartVersion::None,
+            types: Vec::new(),
+            pre_major: false,
+            tag_prefix: default_tag_prefix(),
+            all_tags: false,
+            tag_type: TagType::Annotated,
+            tag_message: default_tag_message(),
+            release_commit_message: default_release_commit_message(),
+            release_commit_trailers: Vec::new(),
+            sign_off: false,
+            sign_commits: false,
+            release_branches: default_release_branches(),
+            remote: default_remote(),
+            push_token_env: default_push_token_env(),
+            packages: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Returns default commit types, where types from the config
+    /// replace default ones with the same name or are appended
+    pub fn commit_types(&self) -> Vec<CommitType> {
+        let mut types = default_types();
+
+        for commit_type in &self.types {
+            match types.iter_mut().find(|t| t.name == commit_type.name) {
+                Some(existing) => *existing = commit_type.clone(),
+                None => types.push(commit_type.clone()),
+            }
+        }
+
+        types
+    }
+
+    /// Returns packages to release. Repository without configured packages
+    /// is released as a single package located in the root.
+    pub fn packages(&self) -> Vec<Package> {
+        if !self.packages.is_empty() {
+            return self
+                .packages
+                .iter()
+                .map(|package| {
+                    let defaults = Package::new(&package.path, package.tag_prefix.clone());
+
+                    Package {
+                        helm: package.helm.clone().or(defaults.helm),
+                        npm: package.npm.clone().or(defaults.npm),
+                        composer: package.composer.clone().or(defaults.composer),
+                        cargo: package.cargo.clone().or(defaults.cargo),
+                        python: package.python.clone().or(defaults.python),
+                        files: package.files.clone(),
+                        version_source: package.version_source.clone(),
+                        ..defaults
+                    }
+                })
+                .collect();
+        }
+
+        vec![Package {
+            path: String::new(),
+            tag_prefix: Some(self.tag_prefix.clone()),
+            helm: self.helm.clone(),
+            npm: self.npm.clone(),
+            composer: self.composer.clone(),
+            cargo: self.cargo.clone(),
+            python: self.python.clone(),
+            files: self.files.clone(),
+            version_source: self.version_source.clone(),
+        }]
+    }
+}
+
+pub fn read_config_file() -> Result<Config, serde_json::Error> {
+    let config_path = Path::new(".version.json");
+
+    match OpenOptions::new().read(true).open(config_path) {
+        Ok(file) => serde_json::from_reader(file),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config {
+            helm: Some(serde_json::Value::String(String::from(".helm/Chart.yaml"))),
+            npm: Some(serde_json::Value::String(String::from("package.json"))),
+            composer: Some(serde_json::Value::String(String::from("composer.json"))),
+            cargo: Some(serde_json::Value::String(String::from("Cargo.toml"))),
+            python: Some(default_python_files()),
+            ..Config::default()
+        }),
+        Err(e) => Err(serde_json::Error::io(e)),
+    }
+}
+
+#[test]
+fn test_commit_types() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "types": [
+                { "type": "perf", "bump": "patch", "section": "Performance Improvements" },
+                { "type": "chore", "bump": "none" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let types = config.commit_types();
+    let names: Vec<&str> = types.iter().map(|t| t.name.as_str()).collect();
+
+    assert_eq!(
+        names,
+        vec!["feat", "fix", "refactor", "docs", "chore", "revert", "perf"]
+    );
+    assert_eq!(
+        types[4],
+        CommitType {
+            name: "chore".to_string(),
+            bump: Bump::None,
+            section: None,
+        }
+    );
+    assert_eq!(types[6].bump.flags(), PATCH_BUMP);
+}
+
+#[test]
+fn test_packages() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "packages": [
+                { "path": "services/api", "npm": ["package.json", "web/package.json"] },
+                { "path": "worker", "tagPrefix": "worker-" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    let packages = config.packages();
+
+    assert_eq!(packages[0].tag_prefix(), "api/v");
+    assert_eq!(
+        packages[0].join_paths(&packages[0].npm),
+        serde_json::json!(["services/api/package.json", "services/api/web/package.json"])
+    );
+    assert_eq!(
+        packages[0].join_paths(&packages[0].helm),
+        serde_json::json!("services/api/.helm/Chart.yaml")
+    );
+    assert_eq!(
+        packages[1].join_paths(&Some(
+            serde_json::json!({ "path": "Chart.yaml", "key": "version" })
+        )),
+        serde_json::json!({ "path": "worker/Chart.yaml", "key": "version" })
+    );
+    assert_eq!(packages[1].tag_prefix(), "worker-");
+    assert_eq!(packages[1].join("CHANGELOG.md"), "worker/CHANGELOG.md");
+    assert_eq!(packages[1].join("!charts/test/*"), "!worker/charts/test/*");
+
+    let root = Config::default().packages();
+
+    assert_eq!(root.len(), 1);
+    assert_eq!(root[0].tag_prefix(), "v");
+    assert_eq!(root[0].join("CHANGELOG.md"), "CHANGELOG.md");
+    assert_eq!(root[0].join_paths(&root[0].npm), serde_json::Value::Null);
+}
+
+#[test]
+fn test_version_files() {
+    let config: Config = serde_json::from_str(
+        r#"{
+            "files": [
+                { "path": "Dockerfile", "regex": "ARG VERSION=(?P<version>\\S+)" },
+                { "path": ["README.md"], "regex": "v(?P<version>[0-9.]+)", "replacement": "v{version}" }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(config.files[0].regex, r"ARG VERSION=(?P<version>\S+)");
+    assert_eq!(
+        config.files[1].replacement,
+        Some(String::from("v{version}"))
+    );
+    assert_eq!(config.packages()[0].files, config.files);
+
+    assert!(serde_json::from_str::<Config>(
+        r#"{ "files": [{ "path": "Dockerfile", "regex": "VERSION=(\\S+)" }] }"#
+    )
+    .is_err());
+    assert!(serde_json::from_str::<Config>(
+        r#"{ "files": [{ "path": "Dockerfile", "regex": "(?P<version>" }] }"#
+    )
+    .is_err());
+}
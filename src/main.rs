@@ -1,16 +1,23 @@
-use std::io::{self, prelude::*};
-use std::path::Path;
-use std::{
-    collections::{BTreeSet, HashMap},
-    env,
-    fs::OpenOptions,
-    process,
-};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::{collections::BTreeSet, env, process};
 
 use git2::Repository;
 use regex::Regex;
 
+mod config;
 mod messages;
+mod signing;
+#[cfg(test)]
+mod test_repo;
+mod transaction;
+mod updaters;
+mod version;
+
+use config::{read_config_file, ChartVersion, CommitType, Config, Package, TagType, VersionFile};
+use transaction::Transaction;
+use updaters::{Format, Updater};
+use version::{Identifier, Version, SEMVER_RX};
 
 const PATCH_BUMP: u8 = 1 << 1;
 const MINOR_BUMP: u8 = 1 << 2;
@@ -41,15 +48,439 @@ fn main() {
         })
         .unwrap();
 
+    // Config, changelog and version files are relative to the repository root
+    let Some(workdir) = repo.workdir() else {
+        eprintln!("Could not release a bare repository");
+        process::exit(1);
+    };
+
+    if let Err(e) = env::set_current_dir(workdir) {
+        eprintln!("Could not change directory to {}: {}", workdir.display(), e);
+        process::exit(1);
+    }
+
+    let config = match read_config_file() {
+        Ok(config) => config,
+        Err(e) => {
+            messages::config_is_invalid(e);
+            process::exit(1);
+        }
+    };
+
+    let dry_run = env::args().any(|item| item == "--dry-run");
+
+    if dry_run {
+        messages::dry_run_enabled();
+    }
+
+    let channel = arg_value("--prerelease");
+    let promote = env::args().any(|item| item == "--promote");
+
+    if promote && channel.is_some() {
+        messages::conflicting_flags("--promote", "--prerelease");
+        process::exit(1);
+    }
+
+    let release_as = arg_value("--release-as").map(|value| {
+        parse_release_as(&value).unwrap_or_else(|| {
+            messages::invalid_release_as(&value);
+            process::exit(1);
+        })
+    });
+
+    if promote && release_as.is_some() {
+        messages::conflicting_flags("--promote", "--release-as");
+        process::exit(1);
+    }
+
+    if let Some(channel) = &channel {
+        let channel_rx = Regex::new(r"^[A-Za-z][0-9A-Za-z-]*$").unwrap();
+
+        if !channel_rx.is_match(channel) {
+            messages::invalid_prerelease_channel(channel);
+            process::exit(1);
+        }
+    }
+
+    let options = Options {
+        dry_run,
+        force: env::args().any(|item| item == "--force" || item == "-f"),
+        promote,
+        channel,
+        release_as,
+    };
+
+    // In dry-run mode failed checks do not stop the preview
+    let skip_checks = env::args().any(|item| item == "--skip-checks");
+    let fatal = !dry_run && !skip_checks;
+
+    let failed_checks = match preflight(&repo, &config.release_branches) {
+        Ok(failed_checks) => failed_checks,
+        Err(e) => {
+            messages::checks_failed(e, fatal);
+
+            if fatal {
+                process::exit(1);
+            }
+
+            Vec::new()
+        }
+    };
+
+    for check in &failed_checks {
+        match check {
+            Preflight::DirtyTree(files) => messages::dirty_tree(files, fatal),
+            Preflight::DetachedHead => messages::detached_head(fatal),
+            Preflight::BranchNotAllowed(branch) => messages::branch_is_not_allowed(branch, fatal),
+            Preflight::BehindUpstream(upstream, behind) => {
+                messages::behind_upstream(upstream, *behind, fatal)
+            }
+        }
+    }
+
+    if !failed_checks.is_empty() && fatal {
+        messages::skip_checks_hint();
+        process::exit(1);
+    }
+
+    let mut packages = config.packages();
+    let monorepo = !config.packages.is_empty();
+
+    if let Some(path) = arg_value("--package") {
+        packages.retain(|package| package.path.trim_end_matches('/') == path.trim_end_matches('/'));
+
+        if packages.is_empty() {
+            messages::package_not_found(path);
+            process::exit(1);
+        }
+    }
+
+    let mut new_tags = Vec::new();
+
+    for package in &packages {
+        if monorepo {
+            messages::releasing_package(&package.path);
+        }
+
+        new_tags.extend(release(&repo, &config, &options, package));
+    }
+
+    // In a monorepo it is fine when only some of packages have changes
+    if new_tags.is_empty() {
+        process::exit(if monorepo { 0 } else { 1 });
+    }
+
+    // Everything below changes the remote, so in dry-run mode
+    // we only report what would have been done.
+    if dry_run {
+        process::exit(0);
+    }
+
+    let branch = current_branch(&repo).unwrap_or_else(|| String::from("HEAD"));
+
+    if !env::args().any(|item| item == "--push" || item == "-p") {
+        messages::push_changes_hint(&config.remote, &branch);
+        return;
+    }
+
+    if repo.find_remote(&config.remote).is_err() {
+        messages::remote_not_found(&config.remote);
+        process::exit(1);
+    }
+
+    match push(&repo, &config.remote, &new_tags, &config.push_token_env) {
+        Ok(()) => messages::changes_pushed(&config.remote, &branch, &new_tags),
+        Err(e) => {
+            messages::push_failed(&config.remote, e.message());
+            process::exit(1);
+        }
+    }
+}
+
+/// Pushes the checked out branch and the tags to the remote. Credentials are taken
+/// from the token environment variable, ssh-agent or git credential helpers.
+fn push(
+    repo: &Repository,
+    remote: &str,
+    tags: &[String],
+    token_env: &str,
+) -> Result<(), git2::Error> {
+    let Some(branch) = current_branch(repo) else {
+        return Err(git2::Error::from_str("HEAD is detached"));
+    };
+
+    let mut refspecs = vec![format!("refs/heads/{0}:refs/heads/{0}", branch)];
+    refspecs.extend(
+        tags.iter()
+            .map(|tag| format!("refs/tags/{0}:refs/tags/{0}", tag)),
+    );
+
+    let config = repo.config()?;
+    let mut remote = repo.find_remote(remote)?;
+    let mut callbacks = git2::RemoteCallbacks::new();
+    let mut attempts = 0;
+
+    callbacks.credentials(|url, username, allowed| {
+        // libgit2 asks again after rejected credentials, which would loop forever
+        attempts += 1;
+
+        if attempts > 3 {
+            return Err(git2::Error::from_str("authentication failed"));
+        }
+
+        if allowed.contains(git2::CredentialType::USER_PASS_PLAINTEXT) {
+            if let Ok(token) = env::var(token_env) {
+                return git2::Cred::userpass_plaintext(username.unwrap_or("oauth2"), &token);
+            }
+
+            if let Ok(cred) = git2::Cred::credential_helper(&config, url, username) {
+                return Ok(cred);
+            }
+        }
+
+        if allowed.contains(git2::CredentialType::SSH_KEY) {
+            return git2::Cred::ssh_key_from_agent(username.unwrap_or("git"));
+        }
+
+        if allowed.contains(git2::CredentialType::USERNAME) {
+            return git2::Cred::username(username.unwrap_or("git"));
+        }
+
+        git2::Cred::default()
+    });
+
+    // Remote may reject a reference, e.g. when the branch is not fast-forward
+    callbacks.push_update_reference(|reference, status| match status {
+        Some(status) => Err(git2::Error::from_str(&format!("{}: {}", reference, status))),
+        None => Ok(()),
+    });
+
+    let mut options = git2::PushOptions::new();
+    options.remote_callbacks(callbacks);
+
+    remote.push(&refspecs, Some(&mut options))
+}
+
+#[test]
+fn test_push() {
+    let test = test_repo::TestRepo::new("push");
+    let (repo, signature) = (&test.repo, &test.signature);
+    // Remote inside `.git` is removed along with the test repository
+    let remote = test.dir.join(".git").join("remote.git");
+    let bare = Repository::init_bare(&remote).unwrap();
+
+    let commit = test.commit("feat: a");
+
+    repo.branch("release/1.x", &commit, true).unwrap();
+    repo.set_head("refs/heads/release/1.x").unwrap();
+    repo.tag("v1.1.0", commit.as_object(), signature, "Release", false)
+        .unwrap();
+    repo.tag_lightweight("v1.0.0", commit.as_object(), false)
+        .unwrap();
+    repo.remote("upstream", remote.to_str().unwrap()).unwrap();
+
+    push(
+        repo,
+        "upstream",
+        &[String::from("v1.1.0")],
+        "VERSION_TEST_TOKEN",
+    )
+    .unwrap();
+
+    assert_eq!(
+        bare.refname_to_id("refs/heads/release/1.x").unwrap(),
+        commit.id()
+    );
+    assert!(bare.find_reference("refs/tags/v1.1.0").is_ok());
+    assert!(bare.find_reference("refs/tags/v1.0.0").is_err());
+    assert!(push(repo, "origin", &[], "VERSION_TEST_TOKEN").is_err());
+}
+
+/// Problem of the repository, which makes release unsafe
+#[derive(Debug, PartialEq)]
+enum Preflight {
+    /// Changed tracked files, which the release commit would include
+    DirtyTree(Vec<String>),
+    DetachedHead,
+    BranchNotAllowed(String),
+    /// Upstream branch and number of its commits missing locally
+    BehindUpstream(String, usize),
+}
+
+/// Checks that the working tree is clean and the checked out branch is a release
+/// branch, which is not behind its upstream (as of the last fetch)
+fn preflight(
+    repo: &Repository,
+    release_branches: &[String],
+) -> Result<Vec<Preflight>, git2::Error> {
+    let mut failed = Vec::new();
+
+    let mut status_options = git2::StatusOptions::new();
+    status_options
+        .include_untracked(false)
+        .include_ignored(false);
+
+    let dirty: Vec<String> = repo
+        .statuses(Some(&mut status_options))?
+        .iter()
+        .filter(|entry| entry.status() != git2::Status::CURRENT)
+        .filter_map(|entry| entry.path().map(String::from))
+        .collect();
+
+    if !dirty.is_empty() {
+        failed.push(Preflight::DirtyTree(dirty));
+    }
+
+    // Without commits there is nothing to check, the release reports it itself
+    let head = match repo.head() {
+        Ok(head) => head,
+        Err(e) if e.code() == git2::ErrorCode::UnbornBranch => return Ok(failed),
+        Err(e) => return Err(e),
+    };
+
+    if !head.is_branch() {
+        failed.push(Preflight::DetachedHead);
+        return Ok(failed);
+    }
+
+    let branch_name = head.shorthand().unwrap_or_default().to_string();
+
+    let allowed = release_branches.iter().any(|pattern| {
+        glob::Pattern::new(pattern).is_ok_and(|pattern| pattern.matches(&branch_name))
+    });
+
+    if !allowed {
+        failed.push(Preflight::BranchNotAllowed(branch_name.clone()));
+    }
+
+    let branch = repo.find_branch(&branch_name, git2::BranchType::Local)?;
+
+    // Branch without upstream is not pushed anywhere yet
+    if let Ok(upstream) = branch.upstream() {
+        let local = head.peel_to_commit()?.id();
+        let remote = upstream.get().peel_to_commit()?.id();
+        let (_, behind) = repo.graph_ahead_behind(local, remote)?;
+
+        if behind > 0 {
+            let name = upstream.name()?.unwrap_or_default().to_string();
+            failed.push(Preflight::BehindUpstream(name, behind));
+        }
+    }
+
+    Ok(failed)
+}
+
+#[test]
+fn test_preflight() {
+    let test = test_repo::TestRepo::new("preflight");
+    let (dir, repo) = (&test.dir, &test.repo);
+    let branches = vec![String::from("main"), String::from("release/*")];
+
+    // Unborn branch has nothing to check
+    assert_eq!(preflight(repo, &branches).unwrap(), vec![]);
+
+    test.write("package.json", "{}");
+    let first = test.commit("feat: a");
+
+    repo.branch("main", &first, true).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+
+    assert_eq!(preflight(repo, &branches).unwrap(), vec![]);
+    assert_eq!(
+        preflight(repo, &[String::from("release/*")]).unwrap(),
+        vec![Preflight::BranchNotAllowed(String::from("main"))]
+    );
+
+    // Remote branch, which is one commit ahead
+    let second = test.commit_with("fix: b", &[&first], None);
+    repo.reference("refs/remotes/origin/main", second.id(), true, "fetch")
+        .unwrap();
+    repo.remote("origin", "https://example.com/repo.git")
+        .unwrap();
+    repo.find_branch("main", git2::BranchType::Local)
+        .unwrap()
+        .set_upstream(Some("origin/main"))
+        .unwrap();
+
+    std::fs::write(dir.join("package.json"), "{ \"version\": \"1.0.0\" }").unwrap();
+
+    assert_eq!(
+        preflight(repo, &branches).unwrap(),
+        vec![
+            Preflight::DirtyTree(vec![String::from("package.json")]),
+            Preflight::BehindUpstream(String::from("origin/main"), 1),
+        ]
+    );
+
+    repo.set_head_detached(first.id()).unwrap();
+
+    assert_eq!(
+        preflight(repo, &branches).unwrap().last(),
+        Some(&Preflight::DetachedHead)
+    );
+}
+
+/// Command line options which affect the release of every package
+struct Options {
+    dry_run: bool,
+    force: bool,
+    promote: bool,
+    channel: Option<String>,
+    release_as: Option<ReleaseAs>,
+}
+
+/// Bumps version of the package, writes its changelog and version files,
+/// then commits them and creates a tag. Returns the created tag or `None`
+/// when there is nothing to release.
+fn release(
+    repo: &Repository,
+    config: &Config,
+    options: &Options,
+    package: &Package,
+) -> Option<String> {
+    let dry_run = options.dry_run;
+    let promote = options.promote;
+    let commit_types = config.commit_types();
+
     // find maximum/latest semver
-    let Ok(all_tags) = tags(&repo).map(|tags| semver(&tags)) else {
+    let tag_prefix = package.tag_prefix();
+
+    let Ok(mut all_tags) =
+        tags(repo, &tag_prefix, config.all_tags).map(|tags| semver(&tags, &tag_prefix))
+    else {
         eprintln!("Could not get tags from repo: git tag -l");
         process::exit(1);
     };
 
+    // Maintenance branch like `release/1.x` continues its own version line
+    let branch = current_branch(repo);
+    let line = branch.as_deref().and_then(maintenance_line);
+
+    if let (Some(line), Some(branch)) = (&line, &branch) {
+        all_tags.retain(|(_, version)| line.contains(version));
+
+        if all_tags.is_empty() && package.version_source.is_none() {
+            messages::no_tags_in_branch_line(branch, &line.to_string());
+            process::exit(1);
+        }
+    }
+
+    // (path, version) from the version source file, which takes precedence over tags
+    let source = package
+        .version_source
+        .as_ref()
+        .map(|_| read_version_source(package.join_paths(&package.version_source), &tag_prefix));
+
     // there is no tags, create one
-    if all_tags.len() == 0 {
-        tag(&repo, "v0.0.1", "Initial release")
+    if all_tags.is_empty() && source.is_none() {
+        let initial_tag = format!("{}{}", tag_prefix, Version::new(0, 0, 1));
+
+        if dry_run {
+            messages::dry_run_tag(initial_tag.clone());
+            return Some(initial_tag);
+        }
+
+        tag(repo, config.tag_type, &initial_tag, "Initial release")
             .map_err(|err| match (err.class(), err.code()) {
                 (git2::ErrorClass::Reference, git2::ErrorCode::NotFound) => {
                     messages::not_enough_commits();
@@ -62,402 +493,2181 @@ fn main() {
             })
             .unwrap();
 
-        messages::initial_tag_created();
-        process::exit(0);
+        messages::initial_tag_created(initial_tag.clone());
+        return Some(initial_tag);
+    }
+
+    let (latest, mismatched_tag) = match latest_release(
+        source.as_ref().map(|(_, version)| version),
+        &all_tags,
+        &tag_prefix,
+    ) {
+        Ok(Some(latest)) => latest,
+        Ok(None) => unreachable!("initial tag is created when there are no tags"),
+        Err(tag) => {
+            let (path, version) = source.as_ref().unwrap();
+            messages::version_source_is_behind(path, &version.to_string(), &tag);
+            process::exit(1);
+        }
+    };
+
+    // The file version wins, but the difference usually means
+    // that the file or the tags were changed by hand
+    if let (Some(tag), Some((path, version))) = (&mismatched_tag, &source) {
+        messages::version_source_mismatch(path, &version.to_string(), tag);
+    }
+    let (latest_tag, latest_version) = (&latest.0, &latest.1);
+
+    if promote && !latest_version.is_prerelease() {
+        messages::nothing_to_promote(latest_tag);
+        process::exit(1);
+    }
+
+    if let Some(ReleaseAs::Version(version)) = &options.release_as {
+        if version <= latest_version {
+            messages::release_as_is_not_greater(version.to_string(), latest_tag.clone());
+            process::exit(1);
+        }
+    }
+
+    // Promoted release should describe all changes since the previous
+    // final release, not only since the last release candidate.
+    let start_rev: String = if all_tags.is_empty() {
+        // Without tags the release includes commits made after the version
+        // in the source file was changed last time
+        let (path, _) = source.as_ref().unwrap();
+
+        match last_commit_touching(repo, path) {
+            Ok(Some(oid)) => oid.to_string(),
+            Ok(None) => {
+                messages::version_source_is_invalid(path, "not committed");
+                process::exit(1);
+            }
+            Err(e) => {
+                eprintln!("Could not get commits: {:?}", e);
+                process::exit(1);
+            }
+        }
+    } else if promote {
+        all_tags
+            .iter()
+            .find(|(_, version)| !version.is_prerelease())
+            .map_or(latest_tag, |(tag, _)| tag)
+            .clone()
+    } else {
+        all_tags[0].0.clone()
+    };
+    let end_rev: String = String::from("HEAD");
+
+    let commits =
+        match get_commits_between_tags(repo, start_rev.as_str(), end_rev.as_str(), &package.path) {
+            Ok(commits) => commits,
+            Err(e) => {
+                eprintln!("Could not get commits between tags: {:?}", e);
+                process::exit(1);
+            }
+        };
+
+    let changelog = make_changelog(commits.clone(), &commit_types);
+
+    let mut bumps = bump_level(&commits, &commit_types);
+
+    if bumps == 0 && !promote && options.release_as.is_none() {
+        if options.force {
+            bumps |= PATCH_BUMP
+        } else if commits.is_empty() {
+            messages::no_commits_between_refs(start_rev, end_rev);
+
+            return None;
+        } else {
+            messages::no_bump_commits_between_refs(start_rev, end_rev);
+
+            return None;
+        }
+    }
+
+    if config.pre_major && latest_version.major == 0 && options.release_as.is_none() {
+        bumps = pre_major_bumps(bumps);
+    }
+
+    let next_version = if promote {
+        Version {
+            pre: Vec::new(),
+            build: Vec::new(),
+            ..latest_version.clone()
+        }
+    } else {
+        match &options.release_as {
+            // Explicit version or bump level ignores the bump required by commits
+            Some(ReleaseAs::Version(version)) => version.clone(),
+            Some(ReleaseAs::Bump(forced)) => bump(*forced, latest_version),
+            None => bump(bumps, latest_version),
+        }
+    };
+
+    let next_version = match &options.channel {
+        Some(channel) => match prerelease(next_version, latest_version, channel) {
+            Ok(version) => version,
+            Err(lower) => {
+                messages::prerelease_is_not_greater(
+                    format!("{}{}", tag_prefix, lower),
+                    latest_tag.clone(),
+                );
+                process::exit(1);
+            }
+        },
+        None => next_version,
+    };
+
+    if let (Some(line), Some(branch)) = (&line, &branch) {
+        if !line.contains(&next_version) {
+            messages::version_is_outside_branch_line(
+                &next_version.to_string(),
+                branch,
+                &line.to_string(),
+            );
+            process::exit(1);
+        }
+    }
+
+    let new_tag = format!("{}{}", tag_prefix, next_version);
+
+    // Tags of other branches are not considered above, but still may have the same version
+    let existing = tags(repo, &tag_prefix, true).map(|tags| semver(&tags, &tag_prefix));
+
+    if let Some((tag, _)) = existing
+        .unwrap_or_default()
+        .into_iter()
+        .find(|(_, version)| version.precedence(&next_version) == std::cmp::Ordering::Equal)
+    {
+        messages::tag_already_exists(&tag);
+        process::exit(1);
+    }
+
+    let changelog_body = if changelog.is_empty() {
+        "*no notable changes*\n"
+    } else {
+        changelog.as_str()
+    };
+
+    let date = chrono::Local::now().format("%F").to_string();
+
+    let changelog_section = format!(
+        "{} {} ({})\n\n{}\n",
+        if next_version.patch != 0 { "###" } else { "##" },
+        new_tag,
+        date,
+        changelog_body
+    );
+
+    let notes = ReleaseNotes {
+        version: &next_version,
+        tag: &new_tag,
+        previous_version: latest_version,
+        previous_tag: latest_tag,
+        date,
+        changelog: changelog_body,
+    };
+
+    let changelog_path = package.join("CHANGELOG.md");
+
+    let file_version = file_version(&tag_prefix, &next_version);
+
+    let json_updater = Updater {
+        key: String::from("$.version"),
+        value: file_version.clone(),
+        regex: Regex::new(r#""version"\s*:\s*"(?P<version>[^"]*)""#).unwrap(),
+        replacement: format!("\"version\": \"{}\"", &file_version),
+    };
+
+    let helm_updater = Updater {
+        key: String::from("appVersion"),
+        value: file_version.clone(),
+        regex: Regex::new(r#"appVersion:[ \t]*(?P<version>.*)"#).unwrap(),
+        replacement: format!("appVersion: {}", &file_version),
+    };
+
+    // Cargo, Python packaging and npm lockfiles require a plain version without the tag prefix
+    let version = next_version.to_string();
+
+    // Files are restored when any of the following steps fails
+    let mut transaction = Transaction::new(dry_run);
+
+    let written = (|| -> io::Result<Vec<String>> {
+        if dry_run {
+            messages::dry_run_changelog(&changelog_path, &changelog_section);
+        } else {
+            prepend_string_to_file(&mut transaction, &changelog_path, &changelog_section)?;
+            messages::write_changelog(&changelog_path);
+        }
+
+        let mut changed_files = vec![changelog_path.clone()];
+
+        changed_files.extend(write_helm_version(
+            package.join_paths(&package.helm),
+            &helm_updater,
+            config.helm_chart_version,
+            release_bump(&next_version, latest_version),
+            &next_version,
+            &mut transaction,
+        )?);
+
+        let npm = write_version(
+            package.join_paths(&package.npm),
+            &json_updater,
+            &mut transaction,
+        )?;
+        let lockfiles = write_npm_lock_version(&npm, &version, &mut transaction)?;
+        changed_files.extend(npm);
+        changed_files.extend(lockfiles);
+
+        changed_files.extend(write_version(
+            package.join_paths(&package.composer),
+            &json_updater,
+            &mut transaction,
+        )?);
+        changed_files.extend(write_cargo_version(
+            package.join_paths(&package.cargo),
+            &version,
+            &latest_version.to_string(),
+            &mut transaction,
+        )?);
+        changed_files.extend(write_python_version(
+            package.join_paths(&package.python),
+            &version,
+            &mut transaction,
+        )?);
+        changed_files.extend(write_regex_version(
+            package,
+            &package.files,
+            &next_version,
+            &new_tag,
+            &mut transaction,
+        )?);
+
+        if package.version_source.is_some() {
+            for path in write_version_source(
+                package.join_paths(&package.version_source),
+                &tag_prefix,
+                &next_version,
+                &mut transaction,
+            )? {
+                if !changed_files.contains(&path) {
+                    changed_files.push(path);
+                }
+            }
+        }
+
+        Ok(changed_files)
+    })();
+
+    let changed_files = match written {
+        Ok(changed_files) => changed_files,
+        Err(e) => {
+            eprintln!("Couldn't write to file: {}", e);
+            abort(&mut transaction);
+        }
+    };
+
+    let changed_files_str = changed_files.join(", ");
+
+    // Everything below changes the repository, so in dry-run mode
+    // we only report what would have been done.
+    if dry_run {
+        messages::dry_run_commit(changed_files_str);
+        messages::dry_run_tag(new_tag.clone());
+        return Some(new_tag);
+    }
+
+    let previous_commit = repo.head().and_then(|head| head.peel_to_commit()).ok();
+
+    if let Err(err) = commit_version_changes(repo, config, &changed_files, &notes) {
+        eprintln!("Could not commit changes to repo: {:?}", err);
+
+        // Files may be already added to the index
+        if let Some(commit) = &previous_commit {
+            let _ = repo.reset_default(Some(commit.as_object()), &changed_files);
+        }
+
+        abort(&mut transaction);
+    }
+
+    messages::committing_files(changed_files_str);
+
+    let tag_message = notes.render(&config.tag_message);
+
+    match tag(repo, config.tag_type, &new_tag, &tag_message) {
+        Ok(_) => {
+            messages::tag_created(new_tag.clone());
+        }
+
+        Err(e) => {
+            eprintln!("Could not create tag: {}", e);
+
+            // Release commit without a tag would be taken for a regular commit by the next run
+            if let Some(commit) = &previous_commit {
+                let _ = repo.reset(commit.as_object(), git2::ResetType::Mixed, None);
+            }
+
+            abort(&mut transaction);
+        }
+    }
+
+    Some(new_tag)
+}
+
+/// Version written to npm, composer and Helm files. It is the tag with the default `v` prefix,
+/// but other prefixes like `app@` or `api/v` would make the version invalid.
+fn file_version(tag_prefix: &str, version: &Version) -> String {
+    if tag_prefix == "v" {
+        format!("v{}", version)
+    } else {
+        version.to_string()
+    }
+}
+
+#[test]
+fn test_release_prefixed_package() {
+    let test = test_repo::TestRepo::new("release");
+    test.write("svc/api/package.json", "{ \"version\": \"1.0.0\" }\n");
+    test.write("svc/api/Chart.yaml", "name: api\nappVersion: 1.0.0\n");
+    let first = test.commit("chore: init");
+    test.repo
+        .tag_lightweight("api/v1.0.0", first.as_object(), false)
+        .unwrap();
+
+    test.write("svc/api/index.js", "");
+    test.commit("feat: endpoint");
+    // Sibling directory sharing the name prefix is another package
+    test.write("svc/api-gw/index.js", "");
+    test.commit("feat!: gateway");
+
+    let config: Config =
+        serde_json::from_str(r#"{ "packages": [{ "path": "svc/api", "helm": "Chart.yaml" }] }"#)
+            .unwrap();
+    let options = Options {
+        dry_run: false,
+        force: false,
+        promote: false,
+        channel: None,
+        release_as: None,
+    };
+
+    let _cwd = test.enter();
+    let released = release(&test.repo, &config, &options, &config.packages()[0]);
+    let read = |path: &str| std::fs::read_to_string(test.dir.join(path)).unwrap();
+
+    assert_eq!(released, Some(String::from("api/v1.1.0")));
+    assert_eq!(read("svc/api/package.json"), "{ \"version\": \"1.1.0\" }\n");
+    assert_eq!(read("svc/api/Chart.yaml"), "name: api\nappVersion: 1.1.0\n");
+    assert!(read("svc/api/CHANGELOG.md").contains("endpoint"));
+    assert!(!read("svc/api/CHANGELOG.md").contains("gateway"));
+    assert!(test.repo.find_reference("refs/tags/api/v1.1.0").is_ok());
+    assert_eq!(file_version("v", &Version::new(1, 1, 0)), "v1.1.0");
+}
+
+#[test]
+fn test_release_dry_run() {
+    let test = test_repo::TestRepo::new("dry-run");
+    let manifest = "[package]\nname = \"app\"\nversion = \"1.0.0\"\n";
+    test.write("package.json", "{ \"version\": \"v1.0.0\" }\n");
+    test.write("Cargo.toml", manifest);
+    let first = test.commit("chore: init");
+    test.repo
+        .tag_lightweight("v1.0.0", first.as_object(), false)
+        .unwrap();
+
+    test.write("src/lib.rs", "");
+    let head = test.commit("feat: library").id();
+
+    let config: Config = serde_json::from_str("{}").unwrap();
+    let options = Options {
+        dry_run: true,
+        force: false,
+        promote: false,
+        channel: None,
+        release_as: None,
+    };
+
+    let _cwd = test.enter();
+    let released = release(&test.repo, &config, &options, &config.packages()[0]);
+    let read = |path: &str| std::fs::read_to_string(test.dir.join(path)).unwrap();
+
+    assert_eq!(released, Some(String::from("v1.1.0")));
+    assert_eq!(read("package.json"), "{ \"version\": \"v1.0.0\" }\n");
+    assert_eq!(read("Cargo.toml"), manifest);
+    assert!(!test.dir.join("CHANGELOG.md").exists());
+    assert_eq!(test.repo.head().unwrap().target(), Some(head));
+    assert!(test.repo.statuses(None).unwrap().is_empty());
+    assert_eq!(
+        test.repo
+            .tag_names(None)
+            .unwrap()
+            .iter()
+            .collect::<Vec<_>>(),
+        vec![Some("v1.0.0")]
+    );
+}
+
+#[test]
+fn test_release_version_source() {
+    let test = test_repo::TestRepo::new("version-source");
+    test.write("VERSION", "1.2.0\n");
+    let first = test.commit("chore: init");
+    test.repo
+        .tag_lightweight("v1.0.0", first.as_object(), false)
+        .unwrap();
+
+    let config: Config = serde_json::from_str(r#"{ "versionSource": "VERSION" }"#).unwrap();
+    let options = Options {
+        dry_run: false,
+        force: false,
+        promote: false,
+        channel: None,
+        release_as: None,
+    };
+
+    let _cwd = test.enter();
+    let read = |path: &str| std::fs::read_to_string(test.dir.join(path)).unwrap();
+
+    test.write("src/lib.rs", "");
+    test.commit("feat: library");
+
+    assert_eq!(
+        release(&test.repo, &config, &options, &config.packages()[0]),
+        Some(String::from("v1.3.0"))
+    );
+    assert_eq!(read("VERSION"), "1.3.0\n");
+
+    // The next release starts from the written version, not from the stale one
+    test.write("src/lib.rs", "//");
+    test.commit("fix: comment");
+
+    assert_eq!(
+        release(&test.repo, &config, &options, &config.packages()[0]),
+        Some(String::from("v1.3.1"))
+    );
+    assert_eq!(read("VERSION"), "1.3.1\n");
+    assert!(test.repo.statuses(None).unwrap().is_empty());
+}
+
+/// Restores files changed by the failed release and exits
+fn abort(transaction: &mut Transaction) -> ! {
+    if !transaction.is_empty() {
+        match transaction.rollback() {
+            Ok(()) => messages::changes_rolled_back(),
+            Err(e) => messages::rollback_failed(e),
+        }
+    }
+
+    process::exit(1);
+}
+
+/// Release details substituted into the commit and tag message templates
+struct ReleaseNotes<'a> {
+    version: &'a Version,
+    tag: &'a str,
+    previous_version: &'a Version,
+    previous_tag: &'a str,
+    date: String,
+    /// Changelog section of the release without its heading
+    changelog: &'a str,
+}
+
+impl ReleaseNotes<'_> {
+    fn render(&self, template: &str) -> String {
+        updaters::render_template(template, self.version, self.tag)
+            .replace("{previousVersion}", &self.previous_version.to_string())
+            .replace("{previousTag}", self.previous_tag)
+            .replace("{date}", &self.date)
+            // Changelog is inserted last, so braces in commit subjects are kept as is
+            .replace("{changelog}", self.changelog.trim_end())
+    }
+}
+
+/// Renders the release commit message followed by the configured trailers
+fn release_commit_message(
+    config: &Config,
+    notes: &ReleaseNotes,
+    signature: &git2::Signature,
+) -> String {
+    let mut trailers: Vec<String> = config
+        .release_commit_trailers
+        .iter()
+        .map(|trailer| notes.render(trailer))
+        .collect();
+
+    if config.sign_off {
+        trailers.push(format!(
+            "Signed-off-by: {} <{}>",
+            String::from_utf8_lossy(signature.name_bytes()),
+            String::from_utf8_lossy(signature.email_bytes())
+        ));
+    }
+
+    let message = notes.render(&config.release_commit_message);
+
+    // Changelog headings start with `#`, so comments are not stripped
+    let message = if trailers.is_empty() {
+        message
+    } else {
+        format!("{}\n\n{}", message.trim_end(), trailers.join("\n"))
+    };
+
+    git2::message_prettify(message, None).unwrap_or_default()
+}
+
+#[test]
+fn test_release_commit_message() {
+    let (version, previous) = (Version::new(1, 3, 0), Version::new(1, 2, 0));
+    let notes = ReleaseNotes {
+        version: &version,
+        tag: "v1.3.0",
+        previous_version: &previous,
+        previous_tag: "v1.2.0",
+        date: String::from("2024-05-01"),
+        changelog: "### Features\n- {tag} placeholder (xf0)\n",
+    };
+    let signature = git2::Signature::now("Test", "test@example.com").unwrap();
+
+    assert_eq!(
+        release_commit_message(&Config::default(), &notes, &signature),
+        "chore(release): v1.3.0\n"
+    );
+
+    let config: Config = serde_json::from_str(
+        r#"{
+            "releaseCommitMessage": "chore(release): {version} ({date})\n\n{previousTag}...{tag}\n\n{changelog}",
+            "releaseCommitTrailers": ["[skip ci]"],
+            "signOff": true
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        release_commit_message(&config, &notes, &signature),
+        indoc::indoc! {"
+            chore(release): 1.3.0 (2024-05-01)
+
+            v1.2.0...v1.3.0
+
+            ### Features
+            - {tag} placeholder (xf0)
+
+            [skip ci]
+            Signed-off-by: Test <test@example.com>
+        "}
+    );
+}
+
+fn commit_version_changes(
+    repo: &Repository,
+    config: &Config,
+    files: &[String],
+    notes: &ReleaseNotes,
+) -> Result<git2::Oid, git2::Error> {
+    let signature = repo
+        .signature()
+        .inspect_err(|_| eprintln!("Could not get signature: git config --global user.name"))?;
+
+    let mut index = repo.index()?;
+    for file in files {
+        index.add_path(Path::new(file.as_str()))?
+    }
+
+    index.write()?;
+    let tree_id = index.write_tree()?;
+    let tree = repo.find_tree(tree_id)?;
+    let head = repo.head()?;
+    let last_commit = head.peel_to_commit()?;
+
+    let message = release_commit_message(config, notes, &signature);
+
+    let gpg_sign = repo.config()?.get_bool("commit.gpgSign").unwrap_or(false);
+
+    if config.sign_commits || gpg_sign {
+        return signing::commit(repo, &signature, &message, &tree, &[&last_commit]);
+    }
+
+    repo.commit(
+        Some("HEAD"),
+        &signature,
+        &signature,
+        &message,
+        &tree,
+        &[&last_commit],
+    )
+}
+
+/// Next version requested by the `--release-as` argument
+#[derive(Debug, PartialEq)]
+enum ReleaseAs {
+    Version(Version),
+    Bump(u8),
+}
+
+/// Parses `major`, `minor`, `patch` or an explicit version, optionally prefixed with `v`
+fn parse_release_as(value: &str) -> Option<ReleaseAs> {
+    match value {
+        "major" => Some(ReleaseAs::Bump(MAJOR_BUMP)),
+        "minor" => Some(ReleaseAs::Bump(MINOR_BUMP)),
+        "patch" => Some(ReleaseAs::Bump(PATCH_BUMP)),
+        _ => value
+            .strip_prefix('v')
+            .unwrap_or(value)
+            .parse()
+            .ok()
+            .map(ReleaseAs::Version),
+    }
+}
+
+#[test]
+fn test_parse_release_as() {
+    assert_eq!(parse_release_as("minor"), Some(ReleaseAs::Bump(MINOR_BUMP)));
+    assert_eq!(
+        parse_release_as("v2.0.0"),
+        Some(ReleaseAs::Version(Version::new(2, 0, 0)))
+    );
+    assert_eq!(
+        parse_release_as("2024.1.0-rc.1"),
+        Some(ReleaseAs::Version("2024.1.0-rc.1".parse().unwrap()))
+    );
+    assert_eq!(parse_release_as("huge"), None);
+}
+
+/// Returns value of the `--name value` or `--name=value` argument,
+/// exits when the argument is given without a value
+fn arg_value(name: &str) -> Option<String> {
+    let args: Vec<String> = env::args().collect();
+
+    find_arg_value(&args, name).unwrap_or_else(|_| {
+        messages::flag_requires_value(name);
+        process::exit(1);
+    })
+}
+
+/// Returns an error when the argument is the last one, is followed by another option
+/// or has an empty value
+fn find_arg_value(args: &[String], name: &str) -> Result<Option<String>, ()> {
+    for (i, arg) in args.iter().enumerate() {
+        let value = if arg == name {
+            args.get(i + 1).map(String::as_str)
+        } else if let Some(value) = arg.strip_prefix(format!("{}=", name).as_str()) {
+            Some(value)
+        } else {
+            continue;
+        };
+
+        return match value {
+            Some(value) if !value.is_empty() && !value.starts_with('-') => {
+                Ok(Some(value.to_string()))
+            }
+            _ => Err(()),
+        };
+    }
+
+    Ok(None)
+}
+
+#[test]
+fn test_find_arg_value() {
+    let args = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+
+    assert_eq!(
+        find_arg_value(&args("version --prerelease rc"), "--prerelease"),
+        Ok(Some(String::from("rc")))
+    );
+    assert_eq!(
+        find_arg_value(&args("version --package=svc/api"), "--package"),
+        Ok(Some(String::from("svc/api")))
+    );
+    assert_eq!(
+        find_arg_value(&args("version --dry-run"), "--prerelease"),
+        Ok(None)
+    );
+    assert_eq!(
+        find_arg_value(&args("version --prerelease"), "--prerelease"),
+        Err(())
+    );
+    assert_eq!(
+        find_arg_value(&args("version --package --dry-run"), "--package"),
+        Err(())
+    );
+    assert_eq!(
+        find_arg_value(&args("version --release-as="), "--release-as"),
+        Err(())
+    );
+}
+
+/// Returns (path, key) pairs from the config value, which is a path, an object
+/// with `path` and optional `key`, or an array of them
+fn version_files(files: &serde_json::Value) -> Option<Vec<(String, Option<String>)>> {
+    match files {
+        serde_json::Value::Null => Some(Vec::new()),
+        serde_json::Value::String(path) => Some(vec![(path.clone(), None)]),
+        serde_json::Value::Object(file) => Some(vec![(
+            file.get("path")?.as_str()?.to_string(),
+            match file.get("key") {
+                Some(key) => Some(key.as_str()?.to_string()),
+                None => None,
+            },
+        )]),
+        serde_json::Value::Array(many) => {
+            many.iter()
+                .try_fold(Vec::new(), |mut all, file| match file {
+                    serde_json::Value::String(_) | serde_json::Value::Object(_) => {
+                        all.extend(version_files(file)?);
+                        Some(all)
+                    }
+                    _ => None,
+                })
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn test_version_files() {
+    let files = serde_json::json!([
+        "package.json",
+        { "path": "web/package.json" },
+        { "path": "Chart.yaml", "key": "version" },
+    ]);
+
+    assert_eq!(
+        version_files(&files),
+        Some(vec![
+            ("package.json".to_string(), None),
+            ("web/package.json".to_string(), None),
+            ("Chart.yaml".to_string(), Some("version".to_string())),
+        ])
+    );
+
+    assert_eq!(version_files(&serde_json::json!([1])), None);
+    assert_eq!(version_files(&serde_json::json!({ "key": "a" })), None);
+}
+
+/// Keys tried in the version source without explicit `key`: `package.json`, `composer.json`,
+/// `Chart.yaml` (chart version), `Cargo.toml` and `pyproject.toml`
+const VERSION_SOURCE_KEYS: [&str; 5] = [
+    "version",
+    "package.version",
+    "workspace.package.version",
+    "project.version",
+    "tool.poetry.version",
+];
+
+/// Reads the current version from the file configured in `versionSource`.
+/// Files of unknown format, like `VERSION`, contain only the version.
+fn read_version_source(files: serde_json::Value, tag_prefix: &str) -> (String, Version) {
+    let Some((path, key)) = version_files(&files).and_then(|files| files.into_iter().next()) else {
+        messages::path_in_config_is_invalid(files);
+        process::exit(1);
+    };
+
+    let version = std::fs::read_to_string(&path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| parse_version_source(&path, key.as_deref(), &content, tag_prefix));
+
+    match version {
+        Ok(version) => (path, version),
+        Err(e) => {
+            messages::version_source_is_invalid(&path, &e);
+            process::exit(1);
+        }
+    }
+}
+
+/// Finds (key, value) of the version in the content of the version source file. Without `key`
+/// the first of `VERSION_SOURCE_KEYS` found in the file is used, files of unknown format
+/// have no key.
+fn version_source_value(
+    path: &str,
+    key: Option<&str>,
+    content: &str,
+) -> Result<(Option<String>, String), String> {
+    let Some(format) = Format::from_path(path) else {
+        return Ok((None, content.trim().to_string()));
+    };
+
+    let keys = match key {
+        Some(key) => vec![key],
+        None => VERSION_SOURCE_KEYS.to_vec(),
+    };
+
+    for key in keys {
+        if let Some(value) = updaters::get_value(format, content, key)? {
+            return Ok((Some(key.to_string()), value));
+        }
+    }
+
+    Err(String::from("no version"))
+}
+
+/// Finds the version in the content of the version source file
+fn parse_version_source(
+    path: &str,
+    key: Option<&str>,
+    content: &str,
+    tag_prefix: &str,
+) -> Result<Version, String> {
+    let (_, value) = version_source_value(path, key, content)?;
+
+    // Files updated by this tool may contain the whole tag
+    value
+        .strip_prefix(tag_prefix)
+        .or_else(|| value.strip_prefix('v'))
+        .unwrap_or(&value)
+        .parse()
+}
+
+#[test]
+fn test_version_source() {
+    let parse = |path, key, content| {
+        parse_version_source(path, key, content, "app@").map(|version| version.to_string())
+    };
+    let ok = |version: &str| Ok(version.to_string());
+
+    assert_eq!(
+        parse("package.json", None, r#"{ "version": "app@1.2.3" }"#),
+        ok("1.2.3")
+    );
+    assert_eq!(
+        parse("package.json", None, r#"{ "version": "v1.2.3" }"#),
+        ok("1.2.3")
+    );
+    assert_eq!(
+        parse("Chart.yaml", None, "version: 0.1.0\nappVersion: v1.2.3\n"),
+        ok("0.1.0")
+    );
+    assert_eq!(
+        parse("Chart.yaml", Some("appVersion"), "appVersion: v1.2.3\n"),
+        ok("1.2.3")
+    );
+    assert_eq!(
+        parse(
+            "Cargo.toml",
+            None,
+            "[workspace]\nmembers = []\n\n[workspace.package]\nversion = \"0.3.0\"\n"
+        ),
+        ok("0.3.0")
+    );
+    assert_eq!(
+        parse(
+            "pyproject.toml",
+            None,
+            "[project]\nversion = \"2.0.0\"\n\n[tool.poetry]\nversion = \"1.0.0\"\n"
+        ),
+        ok("2.0.0")
+    );
+    assert_eq!(parse("VERSION", None, "1.4.0-rc.1\n"), ok("1.4.0-rc.1"));
+
+    assert_eq!(
+        parse("package.json", None, r#"{ "name": "app" }"#),
+        Err(String::from("no version"))
+    );
+    assert!(parse("VERSION", None, "latest\n").is_err());
+    assert!(parse("package.json", None, "{").is_err());
+}
+
+/// Sets the new version in the content of the version source file,
+/// keeping the tag prefix or `v` when the file contains them
+fn set_version_source(
+    path: &str,
+    key: Option<&str>,
+    content: &str,
+    tag_prefix: &str,
+    version: &Version,
+) -> Result<String, String> {
+    let (key, value) = version_source_value(path, key, content)?;
+
+    let value = if value.starts_with(tag_prefix) {
+        format!("{}{}", tag_prefix, version)
+    } else if value.starts_with('v') {
+        format!("v{}", version)
+    } else {
+        version.to_string()
+    };
+
+    match (Format::from_path(path), key) {
+        (Some(format), Some(key)) => updaters::set_value(format, content, &key, &value)?
+            .ok_or_else(|| String::from("no version")),
+        _ => Ok(content.replacen(content.trim(), &value, 1)),
+    }
+}
+
+#[test]
+fn test_set_version_source() {
+    let version = Version::new(1, 3, 0);
+    let set = |path, key, content, tag_prefix| {
+        set_version_source(path, key, content, tag_prefix, &version)
+    };
+    let ok = |content: &str| Ok(content.to_string());
+
+    assert_eq!(set("VERSION", None, "1.2.0\n", "v"), ok("1.3.0\n"));
+    assert_eq!(
+        set("package.json", None, r#"{ "version": "v1.2.0" }"#, "v"),
+        ok(r#"{ "version": "v1.3.0" }"#)
+    );
+    assert_eq!(
+        set(
+            "package.json",
+            None,
+            r#"{ "version": "app@1.2.0" }"#,
+            "app@"
+        ),
+        ok(r#"{ "version": "app@1.3.0" }"#)
+    );
+    assert_eq!(
+        set(
+            "Chart.yaml",
+            Some("appVersion"),
+            "version: 0.1.0\nappVersion: 1.2.0\n",
+            "v"
+        ),
+        ok("version: 0.1.0\nappVersion: 1.3.0\n")
+    );
+    assert_eq!(
+        set(
+            "pyproject.toml",
+            None,
+            "[project]\nversion = \"1.2.0\"\n",
+            ""
+        ),
+        ok("[project]\nversion = \"1.3.0\"\n")
+    );
+}
+
+/// Writes the new version back to the version source file, so the next release starts from it
+fn write_version_source(
+    files: serde_json::Value,
+    tag_prefix: &str,
+    version: &Version,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let Some((path, key)) = version_files(&files).and_then(|files| files.into_iter().next()) else {
+        return Ok(Vec::new());
+    };
+
+    // The file may be already updated as one of the version files
+    let content = std::fs::read_to_string(&path)?;
+    let replaced = set_version_source(&path, key.as_deref(), &content, tag_prefix, version)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path, e)))?;
+
+    if replaced == content {
+        return Ok(Vec::new());
+    }
+
+    write_version_file(transaction, &path, &content, &replaced)?;
+
+    Ok(vec![path])
+}
+
+/// Latest (tag, version) and the latest tag, which differs from it
+type LatestRelease = ((String, Version), Option<String>);
+
+/// Returns the latest (tag, version), where the version source wins over tags,
+/// and the latest tag when it differs from the version source.
+/// Returns the latest tag as an error when the version source is behind it,
+/// since the release would go backwards.
+fn latest_release(
+    source: Option<&Version>,
+    tags: &[(String, Version)],
+    tag_prefix: &str,
+) -> Result<Option<LatestRelease>, String> {
+    match (source, tags.first()) {
+        (Some(version), Some((tag, latest))) if version < latest => Err(tag.clone()),
+        (Some(version), latest) => Ok(Some((
+            (format!("{}{}", tag_prefix, version), version.clone()),
+            latest
+                .filter(|(_, v)| v != version)
+                .map(|(tag, _)| tag.clone()),
+        ))),
+        (None, latest) => Ok(latest.map(|latest| (latest.clone(), None))),
+    }
+}
+
+#[test]
+fn test_latest_release() {
+    let tags = vec![
+        (String::from("v1.2.0"), Version::new(1, 2, 0)),
+        (String::from("v1.1.0"), Version::new(1, 1, 0)),
+    ];
+    let source = Version::new(1, 3, 0);
+
+    assert_eq!(
+        latest_release(Some(&source), &tags, "v"),
+        Ok(Some((
+            (String::from("v1.3.0"), source.clone()),
+            Some(String::from("v1.2.0"))
+        )))
+    );
+    assert_eq!(
+        latest_release(Some(&Version::new(1, 2, 0)), &tags, "v"),
+        Ok(Some((tags[0].clone(), None)))
+    );
+    assert_eq!(
+        latest_release(Some(&Version::new(1, 1, 0)), &tags, "v"),
+        Err(String::from("v1.2.0"))
+    );
+    assert_eq!(
+        latest_release(Some(&source), &[], "v"),
+        Ok(Some(((String::from("v1.3.0"), source.clone()), None)))
+    );
+    assert_eq!(
+        latest_release(None, &tags, "v"),
+        Ok(Some((tags[0].clone(), None)))
+    );
+    assert_eq!(latest_release(None, &[], "v"), Ok(None));
+}
+
+/// Returns version files from the config with glob patterns expanded
+fn version_paths(files: serde_json::Value) -> io::Result<Vec<(String, Option<String>)>> {
+    let Some(paths) = version_files(&files) else {
+        messages::path_in_config_is_invalid(&files);
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid path `{}` in config", files),
+        ));
+    };
+
+    expand_globs(paths).map_err(|e| {
+        messages::glob_pattern_is_invalid(&e);
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    })
+}
+
+/// Expands glob patterns like `charts/*/Chart.yaml` or `packages/**/package.json`
+/// relative to the current directory, which is the repository workdir.
+/// Paths starting with `!` exclude matching files, literal paths are kept as is.
+fn expand_globs(
+    paths: Vec<(String, Option<String>)>,
+) -> Result<Vec<(String, Option<String>)>, glob::PatternError> {
+    let excludes = paths
+        .iter()
+        .filter_map(|(path, _)| path.strip_prefix('!'))
+        .map(glob::Pattern::new)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let options = glob::MatchOptions {
+        require_literal_separator: true,
+        ..glob::MatchOptions::new()
+    };
+
+    let mut expanded: Vec<(String, Option<String>)> = Vec::new();
+
+    for (path, key) in paths {
+        if path.starts_with('!') {
+            continue;
+        }
+
+        let matches = if path.contains(['*', '?', '[']) {
+            glob::glob_with(&path, options)?
+                .filter_map(Result::ok)
+                .filter(|p| p.is_file())
+                .map(|p| p.to_string_lossy().to_string())
+                .collect()
+        } else {
+            vec![path]
+        };
+
+        for path in matches {
+            let excluded = excludes
+                .iter()
+                .any(|exclude| exclude.matches_with(&path, options));
+
+            if !excluded && !expanded.iter().any(|(p, _)| *p == path) {
+                expanded.push((path, key.clone()));
+            }
+        }
+    }
+
+    Ok(expanded)
+}
+
+#[test]
+fn test_expand_globs() {
+    let test = test_repo::TestRepo::new("globs");
+    test.write("src/main.rs", "");
+    test.write("src/config.rs", "");
+    test.write("src/nested/lib.rs", "");
+    test.write("Cargo.toml", "");
+
+    let _cwd = test.enter();
+
+    let paths = expand_globs(vec![
+        ("src/*.rs".to_string(), None),
+        ("!src/main.rs".to_string(), None),
+        (
+            "Cargo.toml".to_string(),
+            Some("package.version".to_string()),
+        ),
+        ("src/config.rs".to_string(), None),
+        ("missing.json".to_string(), None),
+    ])
+    .unwrap();
+
+    assert!(paths.contains(&("src/config.rs".to_string(), None)));
+    assert!(paths.contains(&("missing.json".to_string(), None)));
+    assert!(paths.contains(&(
+        "Cargo.toml".to_string(),
+        Some("package.version".to_string())
+    )));
+    assert!(!paths.iter().any(|(path, _)| path == "src/main.rs"));
+    // `*` does not cross directories
+    assert!(!paths.iter().any(|(path, _)| path == "src/nested/lib.rs"));
+    assert_eq!(
+        paths
+            .iter()
+            .filter(|(path, _)| path == "src/config.rs")
+            .count(),
+        1
+    );
+
+    assert!(expand_globs(vec![("!a/[".to_string(), None)]).is_err());
+}
+
+fn write_version(
+    files: serde_json::Value,
+    updater: &Updater,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for (path, content, replaced) in update_versions(version_paths(files)?, updater, false)? {
+        write_version_file(transaction, &path, &content, &replaced)?;
+        changed_in.push(path);
+    }
+
+    Ok(changed_in)
+}
+
+/// Returns (path, content, content with the new version) of the files, which contain version.
+/// Files without version are returned unchanged when `keep_unmatched` is set.
+fn update_versions(
+    paths: Vec<(String, Option<String>)>,
+    updater: &Updater,
+    keep_unmatched: bool,
+) -> Result<Vec<(String, String, String)>, io::Error> {
+    let mut updated = Vec::new();
+
+    for (path, key) in paths {
+        let p = Path::new(&path);
+
+        if !p.exists() {
+            if env::args().any(|item| item == "--verbose" || item == "-v") {
+                messages::file_not_found(path.as_str());
+            }
+
+            // There is default paths for package.json and composer.json
+            // so, if project does not contain these files, we just skip them
+            // and do not stop executing.
+            continue;
+        }
+
+        if !p.is_file() {
+            return Err(io::Error::other(format!("`{}` is not a file!", path)));
+        }
+
+        let buf = std::fs::read_to_string(p)?;
+
+        let key = key.unwrap_or_else(|| updater.key.clone());
+
+        // Regex is used only for files, which format is unknown or could not be parsed
+        let replaced = match Format::from_path(&path)
+            .map(|format| updaters::set_value(format, &buf, &key, &updater.value))
+        {
+            Some(Ok(replaced)) => replaced,
+            Some(Err(e)) => {
+                messages::file_is_not_parsed(path.as_str(), e.as_str());
+
+                updater.regex.is_match(&buf).then(|| {
+                    updater
+                        .regex
+                        .replace(&buf, &updater.replacement)
+                        .to_string()
+                })
+            }
+            None => updater.regex.is_match(&buf).then(|| {
+                updater
+                    .regex
+                    .replace(&buf, &updater.replacement)
+                    .to_string()
+            }),
+        };
+
+        let Some(replaced) = replaced else {
+            if keep_unmatched {
+                updated.push((path, buf.clone(), buf));
+            } else {
+                messages::version_regex_not_match(path.as_str());
+            }
+
+            // "continue" is here because user may not have a version in his file.
+            continue;
+        };
+
+        updated.push((path, buf, replaced));
+    }
+
+    Ok(updated)
+}
+
+/// Sets `appVersion` of Helm charts, and for `Chart.yaml` files also the chart `version`
+/// and versions of local dependencies on the charts bumped in the same release
+fn write_helm_version(
+    files: serde_json::Value,
+    updater: &Updater,
+    chart_version: ChartVersion,
+    bump_flags: u8,
+    version: &Version,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    // Library charts have no `appVersion`, but their `version` is bumped too
+    let keep_unmatched = chart_version != ChartVersion::None;
+    let mut charts = update_versions(version_paths(files)?, updater, keep_unmatched)?;
+
+    if chart_version != ChartVersion::None {
+        // chart directory -> new chart version
+        let mut bumped: Vec<(PathBuf, String)> = Vec::new();
+
+        for (path, _, replaced) in charts.iter_mut() {
+            if !path.ends_with("Chart.yaml") {
+                continue;
+            }
+
+            let new_version = match chart_version {
+                ChartVersion::Bump => {
+                    match updaters::get_value(Format::Yaml, replaced, "version")
+                        .ok()
+                        .flatten()
+                        .and_then(|current| current.parse::<Version>().ok())
+                    {
+                        Some(current) => bump(bump_flags, &current).to_string(),
+                        None => {
+                            messages::version_regex_not_match(path.as_str());
+                            continue;
+                        }
+                    }
+                }
+                _ => version.to_string(),
+            };
+
+            if let Ok(Some(content)) =
+                updaters::set_value(Format::Yaml, replaced, "version", &new_version)
+            {
+                *replaced = content;
+                bumped.push((
+                    normalize_path(Path::new(path).parent().unwrap()),
+                    new_version,
+                ));
+            }
+        }
+
+        for (path, _, replaced) in charts.iter_mut() {
+            let dir = Path::new(path).parent().unwrap().to_path_buf();
+
+            for (key, dependency) in updaters::helm_local_dependencies(replaced) {
+                let dependency = normalize_path(&dir.join(dependency));
+
+                let Some((_, new_version)) = bumped.iter().find(|(dir, _)| *dir == dependency)
+                else {
+                    continue;
+                };
+
+                if let Ok(Some(content)) =
+                    updaters::set_value(Format::Yaml, replaced, &key, new_version)
+                {
+                    *replaced = content;
+                }
+            }
+        }
+    }
+
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for (path, content, replaced) in charts {
+        if replaced == content {
+            messages::version_regex_not_match(path.as_str());
+            continue;
+        }
+
+        write_version_file(transaction, &path, &content, &replaced)?;
+        changed_in.push(path);
+    }
+
+    Ok(changed_in)
+}
+
+/// Removes `.` and `..` components without accessing the file system
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+/// Bump flags matching the difference between the latest and the next version
+fn release_bump(next: &Version, latest: &Version) -> u8 {
+    if next.major != latest.major {
+        MAJOR_BUMP
+    } else if next.minor != latest.minor {
+        MINOR_BUMP
+    } else {
+        PATCH_BUMP
+    }
+}
+
+#[test]
+fn test_release_bump() {
+    let latest: Version = "1.2.3".parse().unwrap();
+
+    for (next, flags) in [
+        ("2.0.0", MAJOR_BUMP),
+        ("1.3.0-rc.1", MINOR_BUMP),
+        ("1.2.4", PATCH_BUMP),
+    ] {
+        assert_eq!(release_bump(&next.parse().unwrap(), &latest), flags);
+    }
+
+    assert_eq!(
+        normalize_path(Path::new("charts/app/../common")),
+        PathBuf::from("charts/common")
+    );
+}
+
+/// Writes the file with the changed version, in dry-run mode only shows the diff
+fn write_version_file(
+    transaction: &mut Transaction,
+    path: &str,
+    content: &str,
+    replaced: &str,
+) -> io::Result<()> {
+    if transaction.dry_run {
+        let diff = similar::TextDiff::from_lines(content, replaced)
+            .unified_diff()
+            .header(&format!("a/{}", path), &format!("b/{}", path))
+            .to_string();
+
+        messages::dry_run_file_diff(path, diff.as_str());
+    } else {
+        transaction.write(Path::new(path), replaced.as_bytes())?;
+
+        messages::file_version_changed(path);
+    }
+
+    Ok(())
+}
+
+/// Sets version in the files with user-defined regular expressions
+fn write_regex_version(
+    package: &Package,
+    files: &[VersionFile],
+    version: &Version,
+    tag: &str,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for file in files {
+        let paths = version_paths(package.join_paths(&Some(file.path.clone())))?;
+
+        // The regex is already checked when the config is read
+        let regex = Regex::new(&file.regex).unwrap();
+
+        for (path, _) in paths {
+            if !Path::new(&path).is_file() {
+                messages::file_not_found(path.as_str());
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&path)?;
+
+            let Some(replaced) = updaters::set_regex_version(
+                &content,
+                &regex,
+                file.replacement.as_deref(),
+                version,
+                tag,
+            ) else {
+                messages::version_regex_not_match(path.as_str());
+                continue;
+            };
+
+            if replaced != content {
+                write_version_file(transaction, &path, &content, &replaced)?;
+                changed_in.push(path);
+            }
+        }
+    }
+
+    Ok(changed_in)
+}
+
+/// Sets the root package version in `package-lock.json` and `npm-shrinkwrap.json`
+/// next to the changed `package.json` files. yarn and pnpm lockfiles
+/// do not contain the version of the root package, so they are not changed.
+fn write_npm_lock_version(
+    manifests: &[String],
+    version: &str,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for manifest in manifests {
+        let dir = Path::new(manifest).parent().unwrap_or(Path::new(""));
+
+        for lockfile in ["package-lock.json", "npm-shrinkwrap.json"] {
+            let path = dir.join(lockfile).to_string_lossy().to_string();
+
+            if !Path::new(&path).is_file() {
+                continue;
+            }
+
+            let content = std::fs::read_to_string(&path)?;
+
+            match updaters::set_npm_lock_version(&content, version) {
+                Ok(Some(replaced)) => {
+                    write_version_file(transaction, &path, &content, &replaced)?;
+                    changed_in.push(path);
+                }
+                Ok(None) => messages::version_regex_not_match(path.as_str()),
+                Err(e) => messages::file_is_not_parsed_skipped(path.as_str(), e.as_str()),
+            }
+        }
+    }
+
+    Ok(changed_in)
+}
+
+/// Sets version in Python project files, see `updaters::set_python_version`
+fn write_python_version(
+    files: serde_json::Value,
+    version: &str,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let paths = version_paths(files)?;
+
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for (path, _) in paths {
+        if !Path::new(&path).is_file() {
+            if env::args().any(|item| item == "--verbose" || item == "-v") {
+                messages::file_not_found(path.as_str());
+            }
+
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+
+        match updaters::set_python_version(&path, &content, version) {
+            Ok(Some(replaced)) => {
+                write_version_file(transaction, &path, &content, &replaced)?;
+                changed_in.push(path);
+            }
+            Ok(None) => messages::version_regex_not_match(path.as_str()),
+            Err(e) => messages::file_is_not_parsed_skipped(path.as_str(), e.as_str()),
+        }
+    }
+
+    Ok(changed_in)
+}
+
+/// Bumps version of the Cargo manifests, their workspace members,
+/// local dependencies on them and the Cargo.lock next to each manifest
+fn write_cargo_version(
+    files: serde_json::Value,
+    version: &str,
+    previous_version: &str,
+    transaction: &mut Transaction,
+) -> Result<Vec<String>, io::Error> {
+    let paths = version_paths(files)?;
+
+    let mut changed_in: Vec<String> = Vec::new();
+
+    for (path, _) in paths {
+        let p = Path::new(&path);
+
+        if !p.is_file() {
+            if env::args().any(|item| item == "--verbose" || item == "-v") {
+                messages::file_not_found(path.as_str());
+            }
+
+            continue;
+        }
+
+        let content = std::fs::read_to_string(p)?;
+
+        let manifest = match updaters::set_cargo_version(&content, version, None) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                messages::file_is_not_parsed_skipped(path.as_str(), e.as_str());
+                continue;
+            }
+        };
+
+        let dir = p.parent().unwrap_or(Path::new(""));
+        let mut bumped = manifest.bumped.clone();
+
+        // (path, original content, content with the new version)
+        let mut manifests = vec![(path.clone(), content, manifest.content)];
+
+        // Previous version of a virtual workspace is the released one
+        let workspace_version = manifest
+            .workspace_version
+            .clone()
+            .or_else(|| manifest.bumped.first().map(|(_, version)| version.clone()))
+            .unwrap_or_else(|| previous_version.to_string());
+
+        for pattern in &manifest.members {
+            let pattern = dir.join(pattern).join("Cargo.toml");
+            let members = glob::glob(&pattern.to_string_lossy())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+            for member in members.filter_map(Result::ok) {
+                let member_path = member.to_string_lossy().to_string();
+
+                let is_excluded = manifest
+                    .exclude
+                    .iter()
+                    .any(|excluded| member.starts_with(dir.join(excluded)));
+
+                if is_excluded || manifests.iter().any(|(path, _, _)| *path == member_path) {
+                    continue;
+                }
+
+                let content = std::fs::read_to_string(&member)?;
+
+                match updaters::set_cargo_version(&content, version, Some(&workspace_version)) {
+                    Ok(member) => {
+                        bumped.extend(member.bumped);
+                        manifests.push((member_path, content, member.content));
+                    }
+                    Err(e) => {
+                        messages::file_is_not_parsed_skipped(member_path.as_str(), e.as_str())
+                    }
+                }
+            }
+        }
+
+        // Virtual workspace has no version itself, only its members do
+        if bumped.is_empty() && manifest.workspace_version.is_none() {
+            messages::version_regex_not_match(path.as_str());
+            continue;
+        }
+
+        let names: Vec<String> = bumped.iter().map(|(name, _)| name.clone()).collect();
+
+        for (path, content, replaced) in manifests {
+            let replaced = updaters::set_cargo_dependency_versions(&replaced, &names, version)
+                .unwrap_or(replaced);
+
+            if replaced != content {
+                write_version_file(transaction, &path, &content, &replaced)?;
+                changed_in.push(path);
+            }
+        }
+
+        let lock = dir.join("Cargo.lock");
+
+        if lock.is_file() {
+            let lock_path = lock.to_string_lossy().to_string();
+            let content = std::fs::read_to_string(&lock)?;
+
+            match updaters::set_cargo_lock_version(&content, &bumped, version) {
+                Ok(replaced) if replaced != content => {
+                    write_version_file(transaction, &lock_path, &content, &replaced)?;
+                    changed_in.push(lock_path);
+                }
+                Ok(_) => {}
+                Err(e) => messages::file_is_not_parsed_skipped(lock_path.as_str(), e.as_str()),
+            }
+        }
+    }
+
+    Ok(changed_in)
+}
+
+#[test]
+fn test_virtual_workspace() {
+    use indoc::indoc;
+
+    let test = test_repo::TestRepo::new("workspace");
+    let read = |path: &str| std::fs::read_to_string(test.dir.join(path)).unwrap();
+
+    let root_manifest = "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/d\"]\n";
+    test.write("Cargo.toml", root_manifest);
+    test.write(
+        "crates/a/Cargo.toml",
+        "[package]\nname = \"a\"\nversion = \"1.0.0\"\n",
+    );
+    // Released on its own
+    test.write(
+        "crates/c/Cargo.toml",
+        "[package]\nname = \"c\"\nversion = \"0.3.0\"\n",
+    );
+    test.write(
+        "crates/d/Cargo.toml",
+        "[package]\nname = \"d\"\nversion = \"1.0.0\"\n",
+    );
+    test.write(
+        "crates/b/Cargo.toml",
+        indoc! {r#"
+            [package]
+            name = "b"
+            version = "1.0.0"
+
+            [dependencies]
+            a = { path = "../a", version = "1.0.0" }
+            c = { path = "../c", version = "0.3.0" }
+        "#},
+    );
+    test.write(
+        "Cargo.lock",
+        indoc! {r#"
+            version = 3
+
+            [[package]]
+            name = "a"
+            version = "1.0.0"
+
+            [[package]]
+            name = "b"
+            version = "1.0.0"
+
+            [[package]]
+            name = "c"
+            version = "0.3.0"
+        "#},
+    );
+
+    let root = test.dir.join("Cargo.toml");
+    let mut transaction = Transaction::new(false);
+    let changed = write_cargo_version(
+        serde_json::json!(root.to_string_lossy()),
+        "1.1.0",
+        "1.0.0",
+        &mut transaction,
+    )
+    .unwrap();
+
+    assert_eq!(changed.len(), 3);
+    assert_eq!(read("Cargo.toml"), root_manifest);
+    assert_eq!(
+        read("crates/a/Cargo.toml"),
+        "[package]\nname = \"a\"\nversion = \"1.1.0\"\n"
+    );
+    assert!(read("crates/b/Cargo.toml").contains("a = { path = \"../a\", version = \"1.1.0\" }"));
+    assert!(read("crates/b/Cargo.toml").contains("c = { path = \"../c\", version = \"0.3.0\" }"));
+    assert_eq!(
+        read("crates/c/Cargo.toml"),
+        "[package]\nname = \"c\"\nversion = \"0.3.0\"\n"
+    );
+    assert_eq!(
+        read("crates/d/Cargo.toml"),
+        "[package]\nname = \"d\"\nversion = \"1.0.0\"\n"
+    );
+    assert!(!read("Cargo.lock").contains("1.0.0"));
+    assert!(read("Cargo.lock").contains("0.3.0"));
+}
+
+fn prepend_string_to_file(
+    transaction: &mut Transaction,
+    path: &str,
+    string: &str,
+) -> io::Result<()> {
+    // Changelog is created by the first release
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e),
+    };
+
+    transaction.write(Path::new(path), format!("{}{}", string, content).as_bytes())
+}
+
+/// Returns (short hash, message) of commits between tags, which change
+/// files inside the path. Empty path means the whole repository.
+fn get_commits_between_tags(
+    repo: &Repository,
+    start_tag: &str,
+    end_tag: &str,
+    path: &str,
+) -> Result<Vec<(String, String)>, git2::Error> {
+    let start_oid = repo.revparse_single(start_tag)?.id();
+    let end_oid = repo.revparse_single(end_tag)?.id();
+
+    let mut rev_walk = repo.revwalk()?;
+
+    rev_walk.push(end_oid)?;
+    rev_walk.hide(start_oid)?;
+
+    let mut commits = Vec::new();
+
+    for oid in rev_walk {
+        let oid = oid?;
+        let commit = repo.find_commit(oid)?;
+
+        if !path.is_empty() && !commit_touches_path(repo, &commit, path)? {
+            continue;
+        }
+
+        let commit_id = commit.id();
+        let id = commit_id.to_string();
+
+        commits.push((
+            id[0..10].to_string(),
+            commit.message().unwrap_or("").to_string(),
+        ));
+    }
+
+    Ok(commits)
+}
+
+/// Returns the latest commit reachable from HEAD, which changes the file
+fn last_commit_touching(repo: &Repository, path: &str) -> Result<Option<git2::Oid>, git2::Error> {
+    let mut rev_walk = repo.revwalk()?;
+
+    rev_walk.push_head()?;
+    rev_walk.set_sorting(git2::Sort::TOPOLOGICAL | git2::Sort::TIME)?;
+
+    for oid in rev_walk {
+        let commit = repo.find_commit(oid?)?;
+
+        if commit_touches_path(repo, &commit, path)? {
+            return Ok(Some(commit.id()));
+        }
     }
 
-    let start_rev: String = all_tags[0].clone().0;
-    let end_rev: String = String::from("HEAD");
+    Ok(None)
+}
 
-    let commits = match get_commits_between_tags(&repo, start_rev.as_str(), end_rev.as_str()) {
-        Ok(commits) => commits,
-        Err(e) => {
-            eprintln!("Could not get commits between tags: {:?}", e);
-            process::exit(1);
-        }
+/// Checks if the commit changes any file inside the path
+/// compared to its first parent
+fn commit_touches_path(
+    repo: &Repository,
+    commit: &git2::Commit,
+    path: &str,
+) -> Result<bool, git2::Error> {
+    let tree = commit.tree()?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
     };
 
-    let changelog = make_changelog(commits.clone());
+    let mut options = git2::DiffOptions::new();
+    options.pathspec(path.trim_end_matches('/'));
 
-    let mut bumps = 0;
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut options))?;
 
-    if commits.len() == 0 {
-        if env::args().any(|item| item == "--force" || item == "-f") {
-            bumps |= PATCH_BUMP
-        } else {
-            messages::no_commits_between_refs(start_rev, end_rev);
+    Ok(diff.deltas().len() > 0)
+}
 
-            process::exit(1);
-        }
+/// Returns the short name of the checked out branch, `None` for detached HEAD
+fn current_branch(repo: &Repository) -> Option<String> {
+    let head = repo.head().ok()?;
+
+    if !head.is_branch() {
+        return None;
     }
 
-    for (_, commit) in commits {
-        if commit.starts_with("fix!") || commit.starts_with("feat!") {
-            bumps |= MAJOR_BUMP;
-            break;
-        }
+    head.shorthand().map(String::from)
+}
 
-        if commit.starts_with("feat") {
-            bumps |= MINOR_BUMP;
-            break;
-        }
+/// Versions released from a maintenance branch: `1.x` for minor and patch
+/// releases of `1`, `1.2.x` for patch releases of `1.2`
+#[derive(Debug, PartialEq)]
+struct Line {
+    major: u64,
+    minor: Option<u64>,
+}
 
-        // Does not include `docs` here, because usually changes
-        // in documentation does not affect main source code
-        // and not require version bump.
-        if ["chore", "fix", "refactor"]
-            .iter()
-            .any(|t| commit.starts_with(t))
-        {
-            bumps |= PATCH_BUMP;
-            break;
-        }
+impl Line {
+    fn contains(&self, version: &Version) -> bool {
+        version.major == self.major && self.minor.is_none_or(|minor| version.minor == minor)
     }
+}
 
-    let new_tag = bump(bumps, all_tags[0].1);
-
-    if let Err(e) = prepend_string_to_file(
-        "CHANGELOG.md",
-        format!(
-            "{} {} ({})\n\n{}\n",
-            if bumps & PATCH_BUMP == PATCH_BUMP {
-                "###"
-            } else {
-                "##"
-            },
-            new_tag,
-            chrono::Local::now().format("%F"),
-            if changelog == "" {
-                "*no notable changes*\n"
-            } else {
-                changelog.as_str()
-            }
-        ),
-    ) {
-        eprintln!("Couldn't write to file: {}", e);
-        process::exit(1);
+impl std::fmt::Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.minor {
+            Some(minor) => write!(f, "{}.{}.x", self.major, minor),
+            None => write!(f, "{}.x", self.major),
+        }
     }
+}
 
-    messages::write_changelog();
+/// Detects version line by the last component of the branch name, e.g. `release/1.x`
+fn maintenance_line(branch: &str) -> Option<Line> {
+    let re = Regex::new(r"(?:^|/)(?P<major>0|[1-9]\d*)\.(?:(?P<minor>0|[1-9]\d*)\.)?x$").unwrap();
+    let caps = re.captures(branch)?;
+
+    Some(Line {
+        major: caps["major"].parse().ok()?,
+        minor: match caps.name("minor") {
+            Some(minor) => Some(minor.as_str().parse().ok()?),
+            None => None,
+        },
+    })
+}
 
-    fn commit_version_changes(
-        repo: &Repository,
-        files: Vec<String>,
-        new_tag: String,
-    ) -> Result<git2::Oid, git2::Error> {
-        let Ok(signature) = repo.signature() else {
-            eprintln!("Could not get signature: git config --global user.name");
-            process::exit(1);
-        };
+#[test]
+fn test_maintenance_line() {
+    let line = maintenance_line("release/1.x").unwrap();
 
-        let mut index = repo.index()?;
-        for file in files {
-            index.add_path(Path::new(file.as_str()))?
+    assert_eq!(
+        line,
+        Line {
+            major: 1,
+            minor: None
         }
+    );
+    assert!(line.contains(&"1.5.0".parse().unwrap()));
+    assert!(!line.contains(&"2.0.0".parse().unwrap()));
 
-        index.write()?;
-        let tree_id = index.write_tree()?;
-        let tree = repo.find_tree(tree_id)?;
-        let head = repo.head()?;
-        let last_commit = head.peel_to_commit()?;
-
-        repo.commit(
-            Some("HEAD"),
-            &signature,
-            &signature,
-            git2::message_prettify(format!("chore(release): {}", new_tag), Some(b'#'))
-                .unwrap()
-                .as_str(),
-            &tree,
-            &[&last_commit],
-        )
-    }
+    let line = maintenance_line("1.2.x").unwrap();
 
-    let config = read_config_file().unwrap();
-    let mut changed_files = vec!["CHANGELOG.md".to_string()];
+    assert_eq!(line.to_string(), "1.2.x");
+    assert!(line.contains(&"1.2.7".parse().unwrap()));
+    assert!(!line.contains(&"1.3.0".parse().unwrap()));
 
-    write_version_by_regex(
-        config.helm.unwrap_or(serde_json::Value::Null),
-        Regex::new(r#"appVersion:\s*(?P<version>.*)"#).unwrap(),
-        format!("appVersion: {}", &new_tag.clone()),
-    )
-    .and_then(|files| Ok(changed_files.extend(files)))
-    .expect("TODO: panic message 1");
+    assert_eq!(maintenance_line("main"), None);
+    assert_eq!(maintenance_line("feature/fix-1.x-docs"), None);
+    assert_eq!(maintenance_line("release/1.x.y"), None);
+}
 
-    write_version_by_regex(
-        config.npm.unwrap_or(serde_json::Value::Null),
-        Regex::new(r#"version":\s*"(?P<version>.*)"#).unwrap(),
-        format!("version\": \"{}\"", &new_tag.clone()),
-    )
-    .and_then(|files| Ok(changed_files.extend(files)))
-    .expect("TODO: explain panic message 2");
+// return tags made of the given prefix and a version, which point to commits,
+// when `all` is not set only tags of commits reachable from HEAD
+fn tags(repo: &Repository, prefix: &str, all: bool) -> Result<BTreeSet<String>, git2::Error> {
+    let re = Regex::new(format!("^{}{}$", regex::escape(prefix), SEMVER_RX).as_str()).unwrap();
 
-    write_version_by_regex(
-        config.composer.unwrap_or(serde_json::Value::Null),
-        Regex::new(r#"version":\s*"(?P<version>.*)"#).unwrap(),
-        format!("version\": \"{}\"", &new_tag.clone()),
-    )
-    .and_then(|files| Ok(changed_files.extend(files)))
-    .expect("TODO: explain panic message 3");
+    // Repository without commits has no reachable tags
+    let head = repo.head().and_then(|head| head.peel_to_commit()).ok();
 
-    let changed_files_str = changed_files.join(", ");
+    let mut tags = BTreeSet::new();
+    for tag in repo.tag_names(None)?.iter().flatten() {
+        if !re.is_match(tag) {
+            continue;
+        }
 
-    commit_version_changes(&repo, changed_files, new_tag.clone())
-        .map_err(|err| {
-            eprintln!("Could not commit changes to repo: {:?}", err);
-            process::exit(1);
-        })
-        .unwrap();
+        // Tags of trees or blobs can not be releases
+        let Ok(commit) = repo
+            .revparse_single(&format!("refs/tags/{}", tag))
+            .and_then(|object| object.peel_to_commit())
+            .map(|commit| commit.id())
+        else {
+            continue;
+        };
 
-    messages::committing_files(changed_files_str);
+        if !all {
+            let Some(head) = &head else {
+                continue;
+            };
 
-    match tag(&repo, &new_tag, "Release") {
-        Ok(_) => {
-            messages::tag_created(new_tag);
+            if commit != head.id() && !repo.graph_descendant_of(head.id(), commit)? {
+                continue;
+            }
         }
 
-        Err(e) => {
-            eprintln!("Could not create tag: {}", e);
-            process::exit(1);
-        }
+        tags.insert(tag.to_string());
     }
+    Ok(tags)
+}
 
-    messages::push_changes_hint();
+#[test]
+fn test_tags() {
+    let test = test_repo::TestRepo::new("tags");
+    let (repo, signature) = (&test.repo, &test.signature);
 
-    if env::args().any(|item| item == "--push" || item == "-p") {
-        let mut remote = match repo.find_remote("origin") {
-            Ok(remote) => remote,
-            Err(_) => {
-                messages::origin_not_found();
-                process::exit(1);
-            }
-        };
+    let first = test.commit("feat: first");
+    let hotfix = test.commit_with("fix: hotfix", &[&first], None);
+    let second = test.commit("feat: second");
 
-        remote.connect(git2::Direction::Push).unwrap();
+    repo.tag_lightweight("v1.0.0", first.as_object(), false)
+        .unwrap();
+    repo.tag("v1.1.0", second.as_object(), signature, "Release", false)
+        .unwrap();
+    repo.tag_lightweight("v2.0.0", hotfix.as_object(), false)
+        .unwrap();
+    repo.tag_lightweight("other-3.0.0", second.as_object(), false)
+        .unwrap();
 
-        remote
-            .push(&["refs/heads/master:refs/heads/master"], None)
-            .unwrap();
-    }
-}
+    let tree = second.tree().unwrap();
+    repo.tag_lightweight("vtree", tree.as_object(), false)
+        .unwrap();
+    repo.tag_lightweight("v3.0.0", tree.as_object(), false)
+        .unwrap();
 
-#[derive(serde_derive::Deserialize, Debug, PartialEq, Clone)]
-struct Config {
-    helm: Option<serde_json::Value>,
-    npm: Option<serde_json::Value>,
-    composer: Option<serde_json::Value>,
+    assert_eq!(
+        tags(repo, "v", false).unwrap(),
+        BTreeSet::from(["v1.0.0".to_string(), "v1.1.0".to_string()])
+    );
+    assert_eq!(tags(repo, "v", true).unwrap().len(), 3);
 }
 
-fn read_config_file() -> Result<Config, serde_json::Error> {
-    let config_path = Path::new(".version.json");
-    let file = OpenOptions::new().read(true).open(config_path);
+/// Converts tags consisting of the prefix and version to (tag, version)
+/// representation, latest version first
+fn semver(tags: &BTreeSet<String>, prefix: &str) -> Vec<(String, Version)> {
+    let re = Regex::new(format!("^{}{}$", regex::escape(prefix), SEMVER_RX).as_str()).unwrap();
+    let mut versions: Vec<(String, Version)> = Vec::new();
 
-    let default_config = Ok(Config {
-        helm: Some(serde_json::Value::String(String::from(".helm/Chart.yaml"))),
-        npm: Some(serde_json::Value::String(String::from("package.json"))),
-        composer: Some(serde_json::Value::String(String::from("composer.json"))),
-    });
+    for tag in tags {
+        if let Some(version) = re
+            .captures(tag)
+            .and_then(|caps| Version::from_captures(&caps))
+        {
+            versions.push((tag.to_string(), version));
+        }
+    }
 
-    file.and_then(|f| serde_json::from_reader(f).map_err(|e| e.into()))
-        .or(default_config)
+    versions.sort_by(|(_, a), (_, b)| b.cmp(a));
+    versions
 }
 
-fn write_version_by_regex(
-    files: serde_json::Value,
-    re: Regex,
-    to: String,
-) -> Result<Vec<String>, io::Error> {
-    let set_version = |paths: Vec<&str>| {
-        let mut changed_in: Vec<String> = Vec::new();
-
-        for path in paths {
-            let p = Path::new(path);
+#[test]
+fn test_semver() {
+    let tags = BTreeSet::from([
+        "v1.2.0-rc.1".to_string(),
+        "v1.2.0-rc.1+build.5".to_string(),
+        "v1.2.0-rc.10".to_string(),
+        "v1.2.0-rc.2".to_string(),
+        "v1.1.9".to_string(),
+        "not-a-version".to_string(),
+        "helm-chart-2.0.0".to_string(),
+        "release-3.0.0".to_string(),
+        "4.0.0".to_string(),
+    ]);
 
-            if !p.exists() {
-                if env::args().any(|item| item == "--verbose" || item == "-v") {
-                    messages::file_not_found(path);
-                }
+    let versions = |prefix| -> Vec<String> {
+        semver(&tags, prefix)
+            .into_iter()
+            .map(|(tag, _)| tag)
+            .collect()
+    };
 
-                // There is default paths for package.json and composer.json
-                // so, if project does not contain these files, we just skip them
-                // and do not stop executing.
-                continue;
-            }
+    assert_eq!(versions("release-"), vec!["release-3.0.0"]);
+    assert_eq!(versions(""), vec!["4.0.0"]);
 
-            if !p.is_file() {
-                return Err(io::Error::new(
-                    io::ErrorKind::Other,
-                    format!("`{}` is not a file!", path),
-                ));
-            }
+    assert_eq!(
+        versions("v"),
+        vec![
+            "v1.2.0-rc.10",
+            "v1.2.0-rc.2",
+            "v1.2.0-rc.1+build.5",
+            "v1.2.0-rc.1",
+            "v1.1.9"
+        ]
+    );
+}
 
-            let mut file = OpenOptions::new().read(true).write(true).open(p)?;
-            let mut buf = String::new();
-            file.read_to_string(&mut buf)?;
+fn sort_commits(strings: &mut [(String, String)], types: &[CommitType]) {
+    let conventional_rx = Regex::new(CONVENTIONAL_COMMIT_RX).unwrap();
 
-            if !re.is_match(&buf) {
-                messages::version_regex_not_match(path);
+    // Commits are ordered as types in config, breaking changes go first
+    strings.sort_by_cached_key(|(_, commit)| {
+        let summary = commit.lines().next().unwrap_or("");
 
-                // "continue" is here because user may not have a version in his file.
-                continue;
-            }
+        let Some(caps) = conventional_rx.captures(summary) else {
+            return (types.len(), true);
+        };
 
-            file.seek(io::SeekFrom::Start(0))?;
-            file.write_all(re.replace(&buf, &to).as_bytes())?;
+        let position = types
+            .iter()
+            .position(|t| t.name == caps["type"])
+            .unwrap_or(types.len());
 
-            messages::file_version_changed(path);
+        let breaking = caps.name("breaking").is_some() || !breaking_changes(commit).is_empty();
 
-            changed_in.push(path.to_string());
-        }
+        (position, !breaking)
+    });
+}
 
-        Ok(changed_in)
-    };
+const CONVENTIONAL_COMMIT_RX: &str =
+    r"^(?P<type>[\w-]+)(?:\((?P<note>[\pP\pN\pL\s]+)\))?(?P<breaking>!)?:(?P<subject>.+)$";
 
-    match files {
-        serde_json::Value::Array(many) => {
-            set_version(many.iter().filter_map(|v| v.as_str()).collect())
-        }
+const FOOTER_RX: &str = r"^(?P<token>BREAKING[ -]CHANGE|[\w-]+)(?::\s|\s#)(?P<value>.*)$";
 
-        serde_json::Value::String(file) => set_version(vec![file.as_str()]),
-        serde_json::Value::Null => Ok(Vec::new()),
+/// Parses Conventional Commits footers (`Token: value` or `Token #value`)
+/// from the commit message. Footers start from the first paragraph after
+/// the summary which begins with a footer token, lines without a token
+/// are continuation of the previous footer value.
+fn footers(message: &str) -> Vec<(String, String)> {
+    let footer_rx = Regex::new(FOOTER_RX).unwrap();
+    let mut footers: Vec<(String, String)> = Vec::new();
+    let mut in_footers = false;
+    let mut paragraph_start = false;
 
-        _ => {
-            messages::path_in_config_is_invalid(files);
-            process::exit(1);
-        }
-    }
-}
+    for line in message.lines().skip(1) {
+        if line.trim().is_empty() {
+            paragraph_start = true;
 
-fn prepend_string_to_file(path: &str, string: String) -> io::Result<()> {
-    // Open the file in read and write mode
-    let mut file = OpenOptions::new()
-        .read(true)
-        .write(true)
-        .create(true)
-        .open(path)?;
+            if let Some((_, value)) = footers.last_mut() {
+                value.push('\n');
+            }
 
-    // Read the existing content of the file into a string
-    let mut content = String::new();
-    file.read_to_string(&mut content)?;
+            continue;
+        }
 
-    // Prepend the given string to the content
-    content = format!("{}{}", string, content);
+        match footer_rx.captures(line) {
+            Some(caps) if in_footers || paragraph_start => {
+                in_footers = true;
+                footers.push((caps["token"].to_string(), caps["value"].to_string()));
+            }
+            _ => {
+                if let Some((_, value)) = footers.last_mut() {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+            }
+        }
 
-    // Go back to the beginning of the file
-    file.seek(io::SeekFrom::Start(0))?;
+        paragraph_start = false;
+    }
 
-    // Write the modified content back to the file
-    file.write_all(content.as_bytes())?;
+    footers
+        .into_iter()
+        .map(|(token, value)| (token, value.trim().to_string()))
+        .collect()
+}
 
-    Ok(())
+/// Returns descriptions of breaking changes mentioned in `BREAKING CHANGE` footers
+fn breaking_changes(message: &str) -> Vec<String> {
+    footers(message)
+        .into_iter()
+        .filter(|(token, _)| token == "BREAKING CHANGE" || token == "BREAKING-CHANGE")
+        .map(|(_, value)| value)
+        .collect()
 }
 
-fn get_commits_between_tags(
-    repo: &Repository,
-    start_tag: &str,
-    end_tag: &str,
-) -> Result<Vec<(String, String)>, git2::Error> {
-    let start_oid = repo.revparse_single(start_tag)?.id();
-    let end_oid = repo.revparse_single(end_tag)?.id();
+#[test]
+fn test_footers() {
+    use indoc::indoc;
 
-    let mut rev_walk = repo.revwalk()?;
+    let message = indoc! {"
+        feat(api): replace users endpoint
 
-    rev_walk.push(end_oid)?;
-    rev_walk.hide(start_oid)?;
+        Users are now paginated.
+        Note: this is not a footer.
 
-    let mut commits = Vec::new();
+        Reviewed-by: Z
+        BREAKING CHANGE: `/users` returns a page
+        instead of a plain list.
+        Refs #133
+    "};
 
-    for oid in rev_walk {
-        let oid = oid?;
-        let commit = repo.find_commit(oid)?;
-        let commit_id = commit.id();
-        let id = commit_id.to_string();
+    assert_eq!(
+        footers(message),
+        vec![
+            ("Reviewed-by".to_string(), "Z".to_string()),
+            (
+                "BREAKING CHANGE".to_string(),
+                "`/users` returns a page\ninstead of a plain list.".to_string()
+            ),
+            ("Refs".to_string(), "133".to_string()),
+        ]
+    );
 
-        commits.push((
-            id[0..10].to_string(),
-            commit.summary().unwrap_or("").to_string(),
-        ));
-    }
+    assert_eq!(
+        breaking_changes(message),
+        vec!["`/users` returns a page\ninstead of a plain list."]
+    );
 
-    Ok(commits)
+    assert!(breaking_changes("feat: x\n\nBREAKING CHANGE is mentioned here").is_empty());
 }
 
-// return tags found in the repository
-fn tags(repo: &Repository) -> Result<BTreeSet<String>, git2::Error> {
-    let mut tags = BTreeSet::new();
-    for tag in repo.tag_names(None)?.iter().flatten() {
-        tags.insert(tag.to_string());
-    }
-    Ok(tags)
-}
+/// Returns the highest bump required by any of the commits
+fn bump_level(commits: &[(String, String)], types: &[CommitType]) -> u8 {
+    let conventional_rx = Regex::new(CONVENTIONAL_COMMIT_RX).unwrap();
+    let mut bumps = 0;
+
+    for (_, message) in commits {
+        let summary = message.lines().next().unwrap_or("");
 
-const SEMVER_RX: &str = r"(?P<major>0|[1-9]\d*)\.(?P<minor>0|[1-9]\d*)\.(?P<patch>0|[1-9]\d*)";
+        let Some(caps) = conventional_rx.captures(summary) else {
+            continue;
+        };
 
-/// Converts all tags to (tag, (major, minor, patch)) representation
-fn semver(tags: &BTreeSet<String>) -> Vec<(String, (usize, usize, usize))> {
-    let re = Regex::new(SEMVER_RX).unwrap();
-    let mut versions: Vec<(String, (usize, usize, usize))> = Vec::new();
+        let type_ = &caps["type"];
 
-    for tag in tags {
-        if let Some(caps) = re.captures(tag) {
-            versions.push((
-                tag.to_string(),
-                (
-                    caps["major"].parse::<usize>().unwrap(),
-                    caps["minor"].parse::<usize>().unwrap(),
-                    caps["patch"].parse::<usize>().unwrap(),
-                ),
-            ));
+        if type_ == "chore" && caps.name("note").map(|n| n.as_str()) == Some("release") {
+            continue;
+        }
+
+        if caps.name("breaking").is_some() || !breaking_changes(message).is_empty() {
+            bumps |= MAJOR_BUMP;
+        } else if let Some(commit_type) = types.iter().find(|t| t.name == type_) {
+            bumps |= commit_type.bump.flags();
         }
     }
 
-    versions.sort_by(|a, b| b.1.cmp(&a.1));
-    versions.truncate(2);
-    versions
+    bumps
 }
 
-fn sort_commits(strings: &mut Vec<(String, String)>) {
-    strings.sort_by(|(_, a), (_, b)| {
-        let order = ["feat!", "feat", "fix!", "fix", "refactor", "docs", "chore"];
-
-        let prefix_a = order
+#[test]
+fn test_bump_level() {
+    let commits = |messages: &[&str]| -> Vec<(String, String)> {
+        messages
             .iter()
-            .position(|&prefix| a.starts_with(prefix))
-            .unwrap_or(order.len());
+            .map(|message| ("xf0".to_string(), message.to_string()))
+            .collect()
+    };
 
-        let prefix_b = order
-            .iter()
-            .position(|&prefix| b.starts_with(prefix))
-            .unwrap_or(order.len());
+    let level = |messages: &[&str]| {
+        let bumps = bump_level(&commits(messages), &config::default_types());
 
-        prefix_a.cmp(&prefix_b)
+        bump(bumps, &Version::new(1, 2, 3)).to_string()
+    };
+
+    assert_eq!(level(&["docs: readme"]), "1.2.3");
+    assert_eq!(level(&["chore(release): v1.2.3"]), "1.2.3");
+    assert_eq!(level(&["fix: a", "docs: b"]), "1.2.4");
+    assert_eq!(level(&["fix: a", "feat: b", "chore: c"]), "1.3.0");
+    assert_eq!(level(&["fix: a", "feat!: b"]), "2.0.0");
+    assert_eq!(level(&["fix: a", "feat(api)!: b"]), "2.0.0");
+    assert_eq!(level(&["docs!: b", "fix: a"]), "2.0.0");
+    assert_eq!(
+        level(&["fix: a", "fix(db): b\n\nBREAKING CHANGE: c"]),
+        "2.0.0"
+    );
+    assert_eq!(level(&["fixed typo", "feature: b"]), "1.2.3");
+
+    let mut types = config::default_types();
+    types.push(CommitType {
+        name: "deps".to_string(),
+        bump: config::Bump::Minor,
+        section: None,
     });
-}
 
-const CONVENTIONAL_COMMIT_RX: &str = r"^(?P<type>fix|feat|docs|refactor|chore|revert|docs|chore)!?(?:\((?P<note>[\pP\pN\pL\s]+)\))?:(?P<subject>.+)$";
+    let bumps = bump_level(&commits(&["fix: a", "deps: b"]), &types);
+    assert_eq!(bump(bumps, &Version::new(1, 2, 3)).to_string(), "1.3.0");
+}
 
-fn make_changelog(commits: Vec<(String, String)>) -> String {
+fn make_changelog(commits: Vec<(String, String)>, types: &[CommitType]) -> String {
     let mut sorted_commits = commits.clone();
 
     // Sorting commits here, because in changelog we wants
     // always same order of headers: feat, fix, chore
-    sort_commits(&mut sorted_commits);
+    sort_commits(&mut sorted_commits, types);
 
     let conventional_rx = Regex::new(CONVENTIONAL_COMMIT_RX).unwrap();
     let mut last_type = String::new();
     let mut result = String::new();
+    let mut breaking = String::new();
 
-    let type_replacements: HashMap<String, &str> = HashMap::from([
-        ("feat".to_string(), "Features"),
-        ("fix".to_string(), "Bug Fixes"),
-        ("docs".to_string(), "Documentation"),
-        ("refactor".to_string(), "Code Refactoring"),
-        ("chore".to_string(), "Chores"),
-        ("revert".to_string(), "Reverts"),
-    ]);
+    for (hash, message) in sorted_commits {
+        let commit = message.lines().next().unwrap_or("");
 
-    for (hash, commit) in sorted_commits {
-        if let Some(caps) = conventional_rx.captures(commit.as_str()) {
+        if let Some(caps) = conventional_rx.captures(commit) {
             let Some(type_) = caps.name("type") else {
                 continue;
             };
@@ -468,31 +2678,63 @@ fn make_changelog(commits: Vec<(String, String)>) -> String {
                 }
             }
 
-            if last_type != type_.as_str() && last_type != "" {
-                result.push_str("\n");
+            let note = caps
+                .name("note")
+                .map(|note| format!("**{}:** ", note.as_str()))
+                .unwrap_or_default();
+
+            let mut breaking_notes = breaking_changes(message.as_str());
+
+            // Spec allows to omit the footer when `!` is used,
+            // then commit description is the breaking change.
+            if breaking_notes.is_empty() && caps.name("breaking").is_some() {
+                breaking_notes.extend(caps.name("subject").map(|s| s.as_str().trim().to_string()));
+            }
+
+            for breaking_note in breaking_notes {
+                breaking.push_str(&format!(
+                    "- {}{} ({})\n",
+                    note,
+                    breaking_note.replace('\n', "\n  "),
+                    hash
+                ));
+            }
+
+            // Types without a section are not listed in changelog,
+            // but their breaking changes are still important.
+            let Some(section) = types
+                .iter()
+                .find(|t| t.name == type_.as_str())
+                .and_then(|t| t.section.as_ref())
+            else {
+                continue;
+            };
+
+            if last_type != type_.as_str() && !last_type.is_empty() {
+                result.push('\n');
             }
 
             if last_type != type_.as_str() {
                 last_type = type_.as_str().into();
-                if let Some(replacement) = type_replacements.get(type_.as_str()) {
-                    result.push_str(&format!("### {}\n", replacement));
-                }
+                result.push_str(&format!("### {}\n", section));
             }
 
             result.push_str("- ");
-            if let Some(note) = caps.name("note") {
-                result.push_str(&format!("**{}:** ", note.as_str()));
-            }
+            result.push_str(&note);
 
             if let Some(subject) = caps.name("subject") {
                 result.push_str(subject.as_str().trim());
                 result.push_str(format!(" ({})", hash).as_str());
-                result.push_str("\n");
+                result.push('\n');
             }
         }
     }
 
-    return result;
+    if !breaking.is_empty() {
+        result = format!("### BREAKING CHANGES\n{}\n{}", breaking, result);
+    }
+
+    result
 }
 
 #[test]
@@ -507,7 +2749,7 @@ fn test_changelog() {
         ("xf2".to_string(), "docs(foo): bar".to_string()),
     ];
 
-    let changelog = make_changelog(commits);
+    let changelog = make_changelog(commits, &config::default_types());
 
     assert_eq!(
         changelog,
@@ -527,32 +2769,219 @@ fn test_changelog() {
     )
 }
 
-// create a tag: git tag -a bump -m bump
-fn tag(repo: &Repository, tag: &str, message: &str) -> Result<git2::Oid, git2::Error> {
+#[test]
+fn test_changelog_breaking_changes() {
+    use indoc::indoc;
+
+    let commits: Vec<(String, String)> = vec![
+        ("xf0".to_string(), "fix: some".to_string()),
+        (
+            "xf1".to_string(),
+            "feat(api): bar\n\nBREAKING CHANGE: drop\nold endpoints".to_string(),
+        ),
+        ("xf2".to_string(), "feat!: baz".to_string()),
+    ];
+
+    assert_eq!(
+        make_changelog(commits, &config::default_types()),
+        indoc! {"
+            ### BREAKING CHANGES
+            - **api:** drop
+              old endpoints (xf1)
+            - baz (xf2)
+
+            ### Features
+            - **api:** bar (xf1)
+            - baz (xf2)
+
+            ### Bug Fixes
+            - some (xf0)
+        "}
+    )
+}
+
+// create a tag: git tag -a bump -m bump, git tag bump or git tag -s bump -m bump
+fn tag(
+    repo: &Repository,
+    tag_type: TagType,
+    tag: &str,
+    message: &str,
+) -> Result<git2::Oid, git2::Error> {
     let obj = repo.revparse_single("HEAD")?;
+
+    if tag_type == TagType::Lightweight {
+        return repo.tag_lightweight(tag, &obj, false);
+    }
+
     let sig = repo.signature()?;
-    repo.tag(tag, &obj, &sig, message, false)
+    // Changelog headings start with `#`, so comments are not stripped
+    let message = git2::message_prettify(message, None)?;
+    let gpg_sign = repo.config()?.get_bool("tag.gpgSign").unwrap_or(false);
+
+    if tag_type == TagType::Signed || gpg_sign {
+        signing::tag(repo, tag, &obj, &sig, &message)
+    } else {
+        repo.tag(tag, &obj, &sig, &message, false)
+    }
+}
+
+#[test]
+fn test_tag() {
+    let test = test_repo::TestRepo::new("tag");
+    let repo = &test.repo;
+    test.commit("feat: first");
+
+    tag(repo, TagType::Lightweight, "v1.0.0", "Release").unwrap();
+    tag(
+        repo,
+        TagType::Annotated,
+        "v1.1.0",
+        "v1.1.0\n\n### Features\n- first",
+    )
+    .unwrap();
+
+    let lightweight = repo.find_reference("refs/tags/v1.0.0").unwrap();
+    let annotated = repo
+        .find_reference("refs/tags/v1.1.0")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+
+    assert!(lightweight.peel_to_tag().is_err());
+    assert_eq!(
+        annotated.message(),
+        Some("v1.1.0\n\n### Features\n- first\n")
+    );
+    assert!(tag(repo, TagType::Annotated, "v1.1.0", "Release").is_err());
 }
 
-// return string containing new semver and optional the current semver
-fn bump(version: u8, (major, minor, patch): (usize, usize, usize)) -> String {
+// return new version, bumped according to the bump flags
+fn bump(version: u8, current: &Version) -> Version {
+    let Version {
+        major,
+        minor,
+        patch,
+        ..
+    } = *current;
+
+    // A pre-release already points to the upcoming version, so it is
+    // released as is, unless the bump requires a higher version.
+    let pre = current.is_prerelease();
+
     if MAJOR_BUMP & version == MAJOR_BUMP {
-        format!("v{}.{}.{}", major + 1, 0, 0)
+        if pre && minor == 0 && patch == 0 {
+            Version::new(major, 0, 0)
+        } else {
+            Version::new(major + 1, 0, 0)
+        }
     } else if MINOR_BUMP & version == MINOR_BUMP {
-        format!("v{}.{}.{}", major, minor + 1, 0)
+        if pre && patch == 0 {
+            Version::new(major, minor, 0)
+        } else {
+            Version::new(major, minor + 1, 0)
+        }
     } else if PATCH_BUMP & version == PATCH_BUMP {
-        format!("v{}.{}.{}", major, minor, patch + 1)
+        if pre {
+            Version::new(major, minor, patch)
+        } else {
+            Version::new(major, minor, patch + 1)
+        }
     } else {
-        String::new()
+        current.clone()
     }
 }
 
 #[test]
 fn test_bump() {
-    assert_eq!(bump(PATCH_BUMP, (1, 0, 0)), "v1.0.1");
-    assert_eq!(bump(MINOR_BUMP, (1, 0, 0)), "v1.1.0");
-    assert_eq!(bump(MAJOR_BUMP, (1, 0, 0)), "v2.0.0");
-    assert_eq!(bump(PATCH_BUMP, (1, 0, 99)), "v1.0.100");
-    assert_eq!(bump(MINOR_BUMP, (1, 99, 1)), "v1.100.0");
-    assert_eq!(bump(MAJOR_BUMP, (99, 99, 99)), "v100.0.0");
+    let bump = |version, current| bump(version, &current).to_string();
+
+    assert_eq!(bump(PATCH_BUMP, Version::new(1, 0, 0)), "1.0.1");
+    assert_eq!(bump(MINOR_BUMP, Version::new(1, 0, 0)), "1.1.0");
+    assert_eq!(bump(MAJOR_BUMP, Version::new(1, 0, 0)), "2.0.0");
+    assert_eq!(bump(PATCH_BUMP, Version::new(1, 0, 99)), "1.0.100");
+    assert_eq!(bump(MINOR_BUMP, Version::new(1, 99, 1)), "1.100.0");
+    assert_eq!(bump(MAJOR_BUMP, Version::new(99, 99, 99)), "100.0.0");
+
+    let rc: Version = "1.2.0-rc.1+build.5".parse().unwrap();
+    assert_eq!(bump(PATCH_BUMP, rc.clone()), "1.2.0");
+    assert_eq!(bump(MINOR_BUMP, rc.clone()), "1.2.0");
+    assert_eq!(bump(MAJOR_BUMP, rc), "2.0.0");
+}
+
+/// Before 1.0.0 breaking changes bump minor and features bump patch,
+/// same as Cargo and npm treat `0.y.z` versions
+fn pre_major_bumps(bumps: u8) -> u8 {
+    if MAJOR_BUMP & bumps == MAJOR_BUMP {
+        MINOR_BUMP
+    } else if MINOR_BUMP & bumps == MINOR_BUMP {
+        PATCH_BUMP
+    } else {
+        bumps
+    }
+}
+
+#[test]
+fn test_pre_major_bumps() {
+    let current = Version::new(0, 4, 2);
+    let bump = |bumps| bump(pre_major_bumps(bumps), &current).to_string();
+
+    assert_eq!(bump(MAJOR_BUMP | MINOR_BUMP | PATCH_BUMP), "0.5.0");
+    assert_eq!(bump(MINOR_BUMP | PATCH_BUMP), "0.4.3");
+    assert_eq!(bump(PATCH_BUMP), "0.4.3");
+}
+
+/// Turns the next version into a pre-release of the given channel.
+/// Continues numbering when the current version is a pre-release of the
+/// same version and channel: `1.3.0-rc.1` -> `1.3.0-rc.2`. The pre-release
+/// is an error when it would sort lower than the current version, e.g. `beta` after `rc`.
+fn prerelease(next: Version, current: &Version, channel: &str) -> Result<Version, Version> {
+    let same_version =
+        (next.major, next.minor, next.patch) == (current.major, current.minor, current.patch);
+
+    let number = match current.pre.as_slice() {
+        [Identifier::AlphaNumeric(c), Identifier::Numeric(n)] if c == channel && same_version => {
+            n + 1
+        }
+        _ => 1,
+    };
+
+    let version = Version {
+        pre: vec![
+            Identifier::AlphaNumeric(channel.to_string()),
+            Identifier::Numeric(number),
+        ],
+        build: Vec::new(),
+        ..next
+    };
+
+    match version.precedence(current) {
+        std::cmp::Ordering::Greater => Ok(version),
+        _ => Err(version),
+    }
+}
+
+#[test]
+fn test_prerelease() {
+    let release = Version::new(1, 2, 0);
+    let rc: Version = "1.3.0-rc.1".parse().unwrap();
+
+    let next = |bumps, current: &Version, channel| {
+        prerelease(bump(bumps, current), current, channel)
+            .map(|version| version.to_string())
+            .map_err(|version| version.to_string())
+    };
+
+    let ok = |version: &str| Ok(version.to_string());
+
+    assert_eq!(next(MINOR_BUMP, &release, "rc"), ok("1.3.0-rc.1"));
+    assert_eq!(next(PATCH_BUMP, &release, "beta"), ok("1.2.1-beta.1"));
+    assert_eq!(next(PATCH_BUMP, &rc, "rc"), ok("1.3.0-rc.2"));
+    assert_eq!(next(MINOR_BUMP, &rc, "rc"), ok("1.3.0-rc.2"));
+    assert_eq!(next(MAJOR_BUMP, &rc, "rc"), ok("2.0.0-rc.1"));
+    // `beta` sorts lower than `rc` of the same version
+    assert_eq!(
+        next(PATCH_BUMP, &rc, "beta"),
+        Err(String::from("1.3.0-beta.1"))
+    );
+    assert_eq!(next(MAJOR_BUMP, &rc, "beta"), ok("2.0.0-beta.1"));
 }
@@ -25,14 +25,15 @@ const WARNING_SYMBOL: Emoji = Emoji::new(ansi_string!("{yellow ⚠}"), ansi_stri
 #[allow(unused)]
 const UNKNOWN_SYMBOL: Emoji = Emoji::new(ansi_string!("{gray ?}"), ansi_string!("{gray ?}"));
 
-pub fn initial_tag_created() {
+pub fn initial_tag_created<S: Into<String>>(tag: S) {
     println!(
-        "{} {}",
+        "{} {} - {}",
         INFO_SYMBOL,
         match locale().as_str() {
-            "ru-RU" => "Был создан первый тэг - v0.0.1",
-            _ => "First tag was created - v0.0.1",
-        }
+            "ru-RU" => "Был создан первый тэг",
+            _ => "First tag was created",
+        },
+        tag.into()
     );
 }
 
@@ -48,10 +49,19 @@ pub fn usage() {
               version -f -v
 
             Параметры:
-              -h, --help     Вывести эту справку и выйти.
-              -f, --force    Поднять версию даже если коммитов нет.
-              -v, --verbose  Выводить дополнительную информацию.
-              --version      Вывести версию и выйти.
+              -h, --help         Вывести эту справку и выйти.
+              -f, --force        Поднять версию даже если коммитов нет.
+              -v, --verbose      Выводить дополнительную информацию.
+              --dry-run          Показать будущий релиз, ничего не изменяя.
+              --prerelease <id>  Создать пре-релиз, например v1.3.0-rc.1.
+              --promote          Выпустить последний пре-релиз как релиз.
+              --release-as <v>   Выпустить указанную версию (1.0.0) или
+                                 поднять major, minor или patch.
+              --package <path>   Выпустить только этот пакет монорепозитория.
+              -p, --push         Отправить ветку и новые теги в remote.
+              --skip-checks      Выпустить несмотря на изменённые файлы,
+                                 неподходящую ветку или отставание от remote.
+              --version          Вывести версию и выйти.
         "}
     } else {
         printdoc! {"
@@ -64,10 +74,19 @@ pub fn usage() {
               version -f -v
         
             Options:
-              -h, --help     Show this message and exit.
-              -f, --force    Force patch bump if there is no commits.
-              -v, --verbose  Increase output verbosity.
-              --version      Show version number and exit.
+              -h, --help         Show this message and exit.
+              -f, --force        Force patch bump if there is no commits.
+              -v, --verbose      Increase output verbosity.
+              --dry-run          Preview the release without changing anything.
+              --prerelease <id>  Create a pre-release, e.g. v1.3.0-rc.1.
+              --promote          Release the latest pre-release as final.
+              --release-as <v>   Release the given version (1.0.0) or
+                                 force major, minor or patch bump.
+              --package <path>   Release only this package of a monorepo.
+              -p, --push         Push the branch and new tags to the remote.
+              --skip-checks      Release despite changed files, a wrong
+                                 branch or being behind the remote.
+              --version          Show version number and exit.
         "};
     }
 }
@@ -109,14 +128,37 @@ pub fn no_commits_between_refs<S: Into<String>>(start_rev: S, end_rev: S) {
     );
 }
 
-pub fn write_changelog() {
+pub fn no_bump_commits_between_refs<S: Into<String>>(start_rev: S, end_rev: S) {
+    eprintln!(
+        "{} {} {} {} {}\n{} {}",
+        WARNING_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "Нет коммитов, требующих новой версии, между",
+            _ => "No commits requiring a version bump between",
+        },
+        start_rev.into(),
+        match locale().as_str() {
+            "ru-RU" => "и",
+            _ => "and",
+        },
+        end_rev.into(),
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "Чтобы все равно поднять версию, используйте флаг --force или -f",
+            _ => "If you want to bump the version anyway use --force or -f flag",
+        },
+    );
+}
+
+pub fn write_changelog<S: Into<String>>(path: S) {
     println!(
-        "{} {}",
+        "{} {} {}",
         SUCCESS_SYMBOL,
         match locale().as_str() {
-            "ru-RU" => "вписываем дополнения в CHANGELOG.md",
-            _ => "outputting changes to CHANGELOG.md",
-        }
+            "ru-RU" => "вписываем дополнения в",
+            _ => "outputting changes to",
+        },
+        path.into()
     );
 }
 
@@ -144,28 +186,57 @@ pub fn tag_created<S: Into<String>>(tag: S) {
     );
 }
 
-pub fn push_changes_hint() {
+pub fn push_changes_hint(remote: &str, branch: &str) {
     println!(
-        "{} {} `git push --follow-tags origin master`",
+        "{} {} `git push --follow-tags {} {}`",
         INFO_SYMBOL,
         match locale().as_str() {
             "ru-RU" => "Чтобы отправить изменения, запустите:",
             _ => "To publish, run:",
-        }
+        },
+        remote,
+        branch,
     );
 }
 
-pub fn origin_not_found() {
+pub fn remote_not_found(remote: &str) {
     eprintln!(
         "{} {}",
         ERROR_SYMBOL,
         match locale().as_str() {
-            "ru-RU" => "Удаленный репозиторий `origin` не найден",
-            _ => "Remote with name `origin` was not found",
+            "ru-RU" => format!("Удаленный репозиторий `{}` не найден", remote),
+            _ => format!("Remote with name `{}` was not found", remote),
         }
     );
 }
 
+pub fn changes_pushed(remote: &str, branch: &str, tags: &[String]) {
+    println!(
+        "{} {} {} {} {}",
+        SUCCESS_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "отправили в",
+            _ => "pushed to",
+        },
+        remote,
+        branch,
+        tags.join(" "),
+    );
+}
+
+pub fn push_failed(remote: &str, error: &str) {
+    eprintln!(
+        "{} {} {}: {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось отправить изменения в",
+            _ => "could not push to",
+        },
+        remote,
+        error,
+    );
+}
+
 pub fn file_not_found<S: Into<String>>(path: S) {
     eprintln!(
         "{} {} `{}`, {}",
@@ -198,6 +269,40 @@ pub fn version_regex_not_match<S: Into<String>>(path: S) {
     );
 }
 
+pub fn file_is_not_parsed<S: Into<String>>(path: S, error: S) {
+    eprintln!(
+        "{} {} `{}` ({}), {}",
+        WARNING_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось разобрать файл",
+            _ => "could not parse file",
+        },
+        path.into(),
+        error.into(),
+        match locale().as_str() {
+            "ru-RU" => "ищем версию регулярным выражением",
+            _ => "looking for version with regex",
+        }
+    );
+}
+
+pub fn file_is_not_parsed_skipped<S: Into<String>>(path: S, error: S) {
+    eprintln!(
+        "{} {} `{}` ({}), {}",
+        WARNING_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось разобрать файл",
+            _ => "could not parse file",
+        },
+        path.into(),
+        error.into(),
+        match locale().as_str() {
+            "ru-RU" => "пропускаем его",
+            _ => "skipping it",
+        }
+    );
+}
+
 pub fn file_version_changed<S: Into<String>>(path: S) {
     println!(
         "{} {} {}",
@@ -220,3 +325,426 @@ pub fn path_in_config_is_invalid(files: impl ToString) {
         },
     );
 }
+
+pub fn version_source_is_invalid(path: &str, error: &str) {
+    eprintln!(
+        "{} {} `{}`: {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось прочитать версию из",
+            _ => "could not read version from",
+        },
+        path,
+        error,
+    );
+}
+
+pub fn version_source_mismatch(path: &str, version: &str, latest_tag: &str) {
+    eprintln!(
+        "{} {} `{}` ({}) {} {}",
+        WARNING_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "версия в",
+            _ => "version in",
+        },
+        path,
+        version,
+        match locale().as_str() {
+            "ru-RU" => "не совпадает с последним тегом",
+            _ => "does not match the latest tag",
+        },
+        latest_tag,
+    );
+}
+
+pub fn version_source_is_behind(path: &str, version: &str, latest_tag: &str) {
+    eprintln!(
+        "{} {} `{}` ({}) {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "версия в",
+            _ => "version in",
+        },
+        path,
+        version,
+        match locale().as_str() {
+            "ru-RU" => "меньше последнего тега",
+            _ => "is behind the latest tag",
+        },
+        latest_tag,
+    );
+}
+
+pub fn no_tags_in_branch_line(branch: &str, line: &str) {
+    eprintln!(
+        "{} {} {} {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "в ветке",
+            _ => "branch",
+        },
+        branch,
+        match locale().as_str() {
+            "ru-RU" => "нет тегов версий",
+            _ => "has no tags of versions",
+        },
+        line,
+    );
+}
+
+pub fn version_is_outside_branch_line(version: &str, branch: &str, line: &str) {
+    eprintln!(
+        "{} {} {} {} {} ({})\n{} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "версия",
+            _ => "version",
+        },
+        version,
+        match locale().as_str() {
+            "ru-RU" => "не может быть выпущена из ветки",
+            _ => "could not be released from branch",
+        },
+        branch,
+        line,
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "Несовместимые изменения выпускаются из основной ветки",
+            _ => "Release incompatible changes from the main branch",
+        },
+    );
+}
+
+pub fn tag_already_exists(tag: &str) {
+    eprintln!(
+        "{} {} {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "тег",
+            _ => "tag",
+        },
+        tag,
+        match locale().as_str() {
+            "ru-RU" => "уже существует",
+            _ => "already exists",
+        },
+    );
+}
+
+fn check_symbol(fatal: bool) -> Emoji<'static> {
+    if fatal {
+        ERROR_SYMBOL
+    } else {
+        WARNING_SYMBOL
+    }
+}
+
+pub fn dirty_tree(files: &[String], fatal: bool) {
+    eprintln!(
+        "{} {}: {}",
+        check_symbol(fatal),
+        match locale().as_str() {
+            "ru-RU" => "есть незакоммиченные изменения, они попадут в релизный коммит",
+            _ => "there are uncommitted changes, they would get into the release commit",
+        },
+        files.join(", "),
+    );
+}
+
+pub fn checks_failed(error: git2::Error, fatal: bool) {
+    eprintln!(
+        "{} {}: {}",
+        check_symbol(fatal),
+        match locale().as_str() {
+            "ru-RU" => "не удалось проверить репозиторий",
+            _ => "could not check the repository",
+        },
+        error.message()
+    );
+}
+
+pub fn detached_head(fatal: bool) {
+    eprintln!(
+        "{} {}",
+        check_symbol(fatal),
+        match locale().as_str() {
+            "ru-RU" => "HEAD не указывает на ветку (detached HEAD)",
+            _ => "HEAD is detached, check out a branch",
+        },
+    );
+}
+
+pub fn branch_is_not_allowed(branch: &str, fatal: bool) {
+    eprintln!(
+        "{} {} {} {}",
+        check_symbol(fatal),
+        match locale().as_str() {
+            "ru-RU" => "ветка",
+            _ => "branch",
+        },
+        branch,
+        match locale().as_str() {
+            "ru-RU" => "не указана в releaseBranches",
+            _ => "is not listed in releaseBranches",
+        },
+    );
+}
+
+pub fn behind_upstream(upstream: &str, behind: usize, fatal: bool) {
+    eprintln!(
+        "{} {} {} ({})",
+        check_symbol(fatal),
+        match locale().as_str() {
+            "ru-RU" => "ветка отстаёт от",
+            _ => "branch is behind",
+        },
+        upstream,
+        behind,
+    );
+}
+
+pub fn skip_checks_hint() {
+    eprintln!(
+        "{} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "Чтобы выпустить релиз всё равно, используйте флаг --skip-checks",
+            _ => "If you want to release anyway use --skip-checks flag",
+        },
+    );
+}
+
+pub fn changes_rolled_back() {
+    eprintln!(
+        "{} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "изменённые файлы восстановлены",
+            _ => "changed files are restored",
+        },
+    );
+}
+
+pub fn rollback_failed(error: impl ToString) {
+    eprintln!(
+        "{} {}: {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось восстановить изменённые файлы",
+            _ => "could not restore changed files",
+        },
+        error.to_string(),
+    );
+}
+
+pub fn glob_pattern_is_invalid(error: impl ToString) {
+    eprintln!(
+        "{} {}: {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "неверный шаблон пути в .version.json",
+            _ => "invalid path pattern in .version.json",
+        },
+        error.to_string(),
+    );
+}
+
+pub fn dry_run_enabled() {
+    println!(
+        "{} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "пробный запуск: файлы, коммиты и теги не будут изменены",
+            _ => "dry run: no files, commits or tags will be written",
+        }
+    );
+}
+
+pub fn dry_run_changelog<S: Into<String>>(path: S, section: S) {
+    println!(
+        "{} {} {}:\n\n{}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "будет добавлено в",
+            _ => "would output to",
+        },
+        path.into(),
+        section.into()
+    );
+}
+
+pub fn dry_run_file_diff<S: Into<String>>(path: S, diff: S) {
+    println!(
+        "{} {} {}\n{}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "изменили бы версию в",
+            _ => "would change version in",
+        },
+        path.into(),
+        diff.into()
+    );
+}
+
+pub fn dry_run_commit<S: Into<String>>(files: S) {
+    println!(
+        "{} {} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "закоммитили бы",
+            _ => "would commit",
+        },
+        files.into()
+    );
+}
+
+pub fn dry_run_tag<S: Into<String>>(tag: S) {
+    println!(
+        "{} {} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "создали бы тег",
+            _ => "would tag release",
+        },
+        tag.into()
+    );
+}
+
+pub fn flag_requires_value<S: Into<String>>(flag: S) {
+    eprintln!(
+        "{} `{}` {}",
+        ERROR_SYMBOL,
+        flag.into(),
+        match locale().as_str() {
+            "ru-RU" => "требует значение, см. --help",
+            _ => "requires a value, see --help",
+        }
+    );
+}
+
+pub fn conflicting_flags<S: Into<String>>(a: S, b: S) {
+    eprintln!(
+        "{} `{}` {} `{}`",
+        ERROR_SYMBOL,
+        a.into(),
+        match locale().as_str() {
+            "ru-RU" => "нельзя использовать вместе с",
+            _ => "can not be used together with",
+        },
+        b.into()
+    );
+}
+
+pub fn invalid_prerelease_channel<S: Into<String>>(channel: S) {
+    eprintln!(
+        "{} `{}` {}",
+        ERROR_SYMBOL,
+        channel.into(),
+        match locale().as_str() {
+            "ru-RU" => "не подходит для пре-релиза, используйте например rc, beta или alpha",
+            _ => "is not a valid pre-release identifier, use e.g. rc, beta or alpha",
+        }
+    );
+}
+
+pub fn prerelease_is_not_greater<S: Into<String>>(tag: S, latest_tag: S) {
+    eprintln!(
+        "{} {} {} {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "пре-релиз",
+            _ => "pre-release",
+        },
+        tag.into(),
+        match locale().as_str() {
+            "ru-RU" => "был бы меньше последнего тега",
+            _ => "would be lower than the latest tag",
+        },
+        latest_tag.into()
+    );
+}
+
+pub fn nothing_to_promote<S: Into<String>>(tag: S) {
+    eprintln!(
+        "{} {} {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "последний тег",
+            _ => "latest tag",
+        },
+        tag.into(),
+        match locale().as_str() {
+            "ru-RU" => "не является пре-релизом",
+            _ => "is not a pre-release",
+        }
+    );
+}
+
+pub fn config_is_invalid(error: impl ToString) {
+    eprintln!(
+        "{} {}: {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "не удалось прочитать .version.json",
+            _ => "could not read .version.json",
+        },
+        error.to_string()
+    );
+}
+
+pub fn invalid_release_as<S: Into<String>>(version: S) {
+    eprintln!(
+        "{} `{}` {}",
+        ERROR_SYMBOL,
+        version.into(),
+        match locale().as_str() {
+            "ru-RU" => "не является версией semver (1.0.0) или major, minor, patch",
+            _ => "is not a semantic version (1.0.0) or major, minor, patch",
+        }
+    );
+}
+
+pub fn release_as_is_not_greater<S: Into<String>>(version: S, latest_tag: S) {
+    eprintln!(
+        "{} {} {} {} {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "версия",
+            _ => "version",
+        },
+        version.into(),
+        match locale().as_str() {
+            "ru-RU" => "должна быть больше последнего тега",
+            _ => "should be greater than the latest tag",
+        },
+        latest_tag.into()
+    );
+}
+
+pub fn releasing_package<S: Into<String>>(path: S) {
+    println!(
+        "\n{} {} {}",
+        INFO_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "пакет",
+            _ => "package",
+        },
+        path.into()
+    );
+}
+
+pub fn package_not_found<S: Into<String>>(path: S) {
+    eprintln!(
+        "{} {} `{}` {}",
+        ERROR_SYMBOL,
+        match locale().as_str() {
+            "ru-RU" => "пакет",
+            _ => "package",
+        },
+        path.into(),
+        match locale().as_str() {
+            "ru-RU" => "не найден в .version.json",
+            _ => "is not found in .version.json",
+        }
+    );
+}
@@ -0,0 +1,261 @@
+/*!
+ * Signed release commits and tags, using the key configured for git
+ * with `user.signingKey` and `gpg.format`
+ */
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use git2::{Commit, Object, ObjectType, Oid, Repository, Signature, Tree};
+
+/// Creates a commit with the `gpgsig` header and moves HEAD to it, like `git commit -S`
+pub fn commit(
+    repo: &Repository,
+    signature: &Signature,
+    message: &str,
+    tree: &Tree,
+    parents: &[&Commit],
+) -> Result<Oid, git2::Error> {
+    let buffer = repo.commit_create_buffer(signature, signature, message, tree, parents)?;
+    let content = buffer
+        .as_str()
+        .ok_or_else(|| git2::Error::from_str("commit is not valid UTF-8"))?;
+
+    let oid = repo.commit_signed(content, &sign(repo, signature, content)?, None)?;
+
+    let summary = message.lines().next().unwrap_or_default();
+    repo.head()?
+        .set_target(oid, &format!("commit: {}", summary))?;
+
+    Ok(oid)
+}
+
+/// Creates an annotated tag with the signature appended to the message, like `git tag -s`
+pub fn tag(
+    repo: &Repository,
+    name: &str,
+    target: &Object,
+    tagger: &Signature,
+    message: &str,
+) -> Result<Oid, git2::Error> {
+    let mut content = tag_buffer(name, target.id(), target.kind(), tagger, message);
+    content.push_str(&sign(repo, tagger, &content)?);
+
+    let oid = repo.odb()?.write(ObjectType::Tag, content.as_bytes())?;
+    repo.reference(&format!("refs/tags/{}", name), oid, false, "tag: signed")?;
+
+    Ok(oid)
+}
+
+/// Content of a tag object, which is signed
+fn tag_buffer(
+    name: &str,
+    target: Oid,
+    kind: Option<ObjectType>,
+    tagger: &Signature,
+    message: &str,
+) -> String {
+    let when = tagger.when();
+    let offset = when.offset_minutes().abs();
+
+    format!(
+        "object {}\ntype {}\ntag {}\ntagger {} <{}> {} {}{:02}{:02}\n\n{}",
+        target,
+        kind.unwrap_or(ObjectType::Commit).str(),
+        name,
+        String::from_utf8_lossy(tagger.name_bytes()),
+        String::from_utf8_lossy(tagger.email_bytes()),
+        when.seconds(),
+        when.sign(),
+        offset / 60,
+        offset % 60,
+        message
+    )
+}
+
+/// Returns an ASCII-armored detached signature of the content,
+/// made by the same program git uses for the configured `gpg.format`
+fn sign(repo: &Repository, signer: &Signature, content: &str) -> Result<String, git2::Error> {
+    let config = repo.config()?;
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| String::from("openpgp"));
+    let key = config.get_string("user.signingkey").ok();
+
+    // Public keys given literally are written to a file for `ssh-keygen`
+    let mut temporary_key = None;
+
+    let mut command = match format.as_str() {
+        "ssh" => {
+            let key = key.ok_or_else(|| {
+                git2::Error::from_str("user.signingKey is required to sign with SSH")
+            })?;
+
+            let key_file = match key.strip_prefix("key::") {
+                Some(literal) => Some(literal),
+                None if key.starts_with("ssh-") => Some(key.as_str()),
+                None => None,
+            }
+            .map(|literal| {
+                let path = std::env::temp_dir()
+                    .join(format!(".version-signing-key-{}.pub", std::process::id()));
+                fs::write(&path, literal).map(|_| path)
+            })
+            .transpose()
+            .map_err(|e| git2::Error::from_str(&e.to_string()))?;
+
+            let key_path = match &key_file {
+                Some(path) => path.clone(),
+                None => expand_home(&key),
+            };
+            temporary_key = key_file;
+
+            let mut command = Command::new(program(&config, &["gpg.ssh.program"], "ssh-keygen"));
+            command
+                .args(["-Y", "sign", "-n", "git", "-f"])
+                .arg(key_path);
+            command
+        }
+        "openpgp" | "x509" => {
+            let (keys, default) = if format == "x509" {
+                (&["gpg.x509.program"][..], "gpgsm")
+            } else {
+                (&["gpg.openpgp.program", "gpg.program"][..], "gpg")
+            };
+
+            // Same as git, the committer identity selects the key by default
+            let key = key.unwrap_or_else(|| {
+                format!(
+                    "{} <{}>",
+                    String::from_utf8_lossy(signer.name_bytes()),
+                    String::from_utf8_lossy(signer.email_bytes())
+                )
+            });
+
+            let mut command = Command::new(program(&config, keys, default));
+            command.args(["--status-fd=2", "-bsau", key.as_str()]);
+            command
+        }
+        other => {
+            return Err(git2::Error::from_str(&format!(
+                "unsupported gpg.format `{}`",
+                other
+            )))
+        }
+    };
+
+    let output = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .and_then(|mut child| {
+            child
+                .stdin
+                .take()
+                .expect("stdin is piped")
+                .write_all(content.as_bytes())?;
+            child.wait_with_output()
+        });
+
+    if let Some(path) = temporary_key {
+        let _ = fs::remove_file(path);
+    }
+
+    let output = output.map_err(|e| {
+        git2::Error::from_str(&format!("could not run {:?}: {}", command.get_program(), e))
+    })?;
+
+    let signature = String::from_utf8_lossy(&output.stdout).to_string();
+
+    if !output.status.success() || signature.is_empty() {
+        // Status lines requested with `--status-fd` are only for machines
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let reason: Vec<&str> = stderr
+            .lines()
+            .filter(|line| !line.starts_with("[GNUPG:]"))
+            .collect();
+
+        return Err(git2::Error::from_str(&format!(
+            "{:?} failed to sign the data: {}",
+            command.get_program(),
+            reason.join("\n").trim()
+        )));
+    }
+
+    Ok(signature)
+}
+
+/// Signing program from the first set config key or the default one
+fn program(config: &git2::Config, keys: &[&str], default: &str) -> String {
+    keys.iter()
+        .find_map(|key| config.get_string(key).ok())
+        .unwrap_or_else(|| default.to_string())
+}
+
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(relative), Some(home)) => PathBuf::from(home).join(relative),
+        _ => PathBuf::from(path),
+    }
+}
+
+#[test]
+fn test_signed_tag() {
+    let test = crate::test_repo::TestRepo::new("signing");
+    let repo = &test.repo;
+
+    let key = test.dir.join(".git").join("signing-key");
+    let generated = Command::new("ssh-keygen")
+        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+        .arg(&key)
+        .status()
+        .expect("ssh-keygen of OpenSSH is required to test signing");
+
+    assert!(generated.success(), "ssh-keygen could not generate a key");
+
+    let mut config = repo.config().unwrap();
+    config.set_str("gpg.format", "ssh").unwrap();
+    config
+        .set_str("user.signingkey", key.to_str().unwrap())
+        .unwrap();
+
+    let head = test.commit("initial");
+    let tree = head.tree().unwrap();
+
+    // Tagger time zone is written to the tag object by hand
+    let signature = Signature::new("Test", "test@example.com", &git2::Time::new(0, 180)).unwrap();
+    let oid = commit(
+        repo,
+        &signature,
+        "chore(release): v1.0.0\n",
+        &tree,
+        &[&head],
+    )
+    .unwrap();
+    let (commit_signature, _) = repo.extract_signature(&oid, None).unwrap();
+
+    assert_eq!(repo.head().unwrap().target(), Some(oid));
+    assert!(commit_signature
+        .as_str()
+        .unwrap()
+        .starts_with("-----BEGIN SSH SIGNATURE-----"));
+
+    let target = repo.find_object(oid, None).unwrap();
+    tag(repo, "v1.0.0", &target, &signature, "Release\n").unwrap();
+
+    let created = repo
+        .find_reference("refs/tags/v1.0.0")
+        .unwrap()
+        .peel_to_tag()
+        .unwrap();
+    let message = created.message().unwrap();
+
+    assert_eq!(created.name(), Some("v1.0.0"));
+    assert_eq!(created.target_id(), oid);
+    assert_eq!(created.tagger().unwrap().when().offset_minutes(), 180);
+    assert!(message.starts_with("Release\n-----BEGIN SSH SIGNATURE-----"));
+    assert!(tag(repo, "v1.0.0", &target, &signature, "Release\n").is_err());
+}
@@ -0,0 +1,118 @@
+/*!
+ * Temporary git repository for tests, which is removed when dropped
+ */
+
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+use git2::{Commit, Repository, Signature};
+
+/// Tests using paths relative to the current directory hold it locked,
+/// since the current directory is shared by all test threads
+static CURRENT_DIR: Mutex<()> = Mutex::new(());
+
+fn lock_current_dir() -> MutexGuard<'static, ()> {
+    CURRENT_DIR
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+pub struct TestRepo {
+    pub dir: PathBuf,
+    pub repo: Repository,
+    pub signature: Signature<'static>,
+}
+
+impl TestRepo {
+    /// Creates an empty repository `version-test-<name>-<pid>` in the temp directory
+    /// with the committer identity configured
+    pub fn new(name: &str) -> TestRepo {
+        let dir = env::temp_dir().join(format!("version-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let repo = Repository::init(&dir).unwrap();
+        let mut config = repo.config().unwrap();
+        config.set_str("user.name", "Test").unwrap();
+        config.set_str("user.email", "test@example.com").unwrap();
+
+        TestRepo {
+            dir,
+            repo,
+            signature: Signature::now("Test", "test@example.com").unwrap(),
+        }
+    }
+
+    /// Writes the file and adds it to the index
+    pub fn write(&self, path: &str, content: &str) {
+        let full_path = self.dir.join(path);
+        fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        fs::write(full_path, content).unwrap();
+
+        let mut index = self.repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+    }
+
+    /// Commits the index on top of HEAD
+    pub fn commit(&self, message: &str) -> Commit<'_> {
+        let head = self.repo.head().and_then(|head| head.peel_to_commit()).ok();
+        let parents: Vec<&Commit> = head.iter().collect();
+
+        self.commit_with(message, &parents, Some("HEAD"))
+    }
+
+    /// Commits the index with the given parents, moving the reference if any
+    pub fn commit_with(
+        &self,
+        message: &str,
+        parents: &[&Commit],
+        update_ref: Option<&str>,
+    ) -> Commit<'_> {
+        let tree_id = self.repo.index().unwrap().write_tree().unwrap();
+        let tree = self.repo.find_tree(tree_id).unwrap();
+        let oid = self
+            .repo
+            .commit(
+                update_ref,
+                &self.signature,
+                &self.signature,
+                message,
+                &tree,
+                parents,
+            )
+            .unwrap();
+
+        self.repo.find_commit(oid).unwrap()
+    }
+
+    /// Makes the repository root the current directory until the guard is dropped
+    pub fn enter(&self) -> CurrentDir {
+        let lock = lock_current_dir();
+        let previous = env::current_dir().unwrap();
+        env::set_current_dir(&self.dir).unwrap();
+
+        CurrentDir {
+            previous,
+            _lock: lock,
+        }
+    }
+}
+
+impl Drop for TestRepo {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+pub struct CurrentDir {
+    previous: PathBuf,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Drop for CurrentDir {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.previous);
+    }
+}
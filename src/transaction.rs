@@ -0,0 +1,118 @@
+/*!
+ * Crash-safe file writes, which are undone when any step of the release fails
+ */
+
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Files changed by the release with their original content
+pub struct Transaction {
+    pub dry_run: bool,
+    /// Original content in order of the first change, `None` for created files
+    originals: Vec<(PathBuf, Option<Vec<u8>>)>,
+}
+
+impl Transaction {
+    pub fn new(dry_run: bool) -> Transaction {
+        Transaction {
+            dry_run,
+            originals: Vec::new(),
+        }
+    }
+
+    /// Replaces the file content atomically, remembering the original content
+    pub fn write(&mut self, path: &Path, content: &[u8]) -> io::Result<()> {
+        if !self.originals.iter().any(|(p, _)| p == path) {
+            let original = match fs::read(path) {
+                Ok(original) => Some(original),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+                Err(e) => return Err(e),
+            };
+
+            self.originals.push((path.to_path_buf(), original));
+        }
+
+        write_atomic(path, content)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.originals.is_empty()
+    }
+
+    /// Restores original content of all changed files and removes created ones
+    pub fn rollback(&mut self) -> io::Result<()> {
+        while let Some((path, original)) = self.originals.pop() {
+            match original {
+                Some(content) => write_atomic(&path, &content)?,
+                None => fs::remove_file(&path)?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes content to a temporary file next to the target and renames it over
+/// the target, so the file is either completely old or completely new
+pub fn write_atomic(path: &Path, content: &[u8]) -> io::Result<()> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not a file"))?;
+
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(name);
+    temp_name.push(".version-tmp");
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = (|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(content)?;
+        file.sync_all()?;
+
+        // Keep permissions of the original file, e.g. executable scripts
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&temp_path, metadata.permissions())?;
+        }
+
+        fs::rename(&temp_path, path)
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+#[test]
+fn test_rollback() {
+    let dir = std::env::temp_dir().join(format!("version-test-rollback-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+
+    let changed = dir.join("package.json");
+    let created = dir.join("CHANGELOG.md");
+    fs::write(&changed, "{ \"version\": \"v10.0.0\" }").unwrap();
+
+    let mut transaction = Transaction::new(false);
+    transaction
+        .write(&changed, b"{ \"version\": \"v9.9.9\" }")
+        .unwrap();
+    transaction.write(&changed, b"{}").unwrap();
+    transaction.write(&created, b"## v9.9.9").unwrap();
+
+    assert_eq!(fs::read_to_string(&changed).unwrap(), "{}");
+    assert_eq!(fs::read_to_string(&created).unwrap(), "## v9.9.9");
+
+    transaction.rollback().unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&changed).unwrap(),
+        "{ \"version\": \"v10.0.0\" }"
+    );
+    assert!(!created.exists());
+    assert!(transaction.is_empty());
+    assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+    fs::remove_dir_all(dir).unwrap();
+}
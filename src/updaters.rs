@@ -0,0 +1,1204 @@
+/*!
+ * Format-aware version updaters. They change only the value at the key path
+ * and keep formatting, comments and key order of the rest of the file.
+ */
+
+use std::path::Path;
+
+use regex::{Captures, Regex};
+
+use crate::version::Version;
+
+/// Describes how to change version in files of some kind
+pub struct Updater {
+    /// Key path for structured formats, e.g. `$.version`
+    pub key: String,
+    /// New value of the key
+    pub value: String,
+    /// Fallback for files of unknown format, replaces the match with `replacement`
+    pub regex: Regex,
+    pub replacement: String,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+}
+
+impl Format {
+    /// Detects format by the file extension
+    pub fn from_path(path: &str) -> Option<Format> {
+        match Path::new(path).extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "yaml" | "yml" => Some(Format::Yaml),
+            "toml" => Some(Format::Toml),
+            _ => None,
+        }
+    }
+}
+
+/// Splits key path like `$.version` or `package.version` into keys
+pub fn key_path(key: &str) -> Vec<String> {
+    key.trim_start_matches('$')
+        .trim_start_matches('.')
+        .split('.')
+        .filter(|k| !k.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Sets the string value at the key path. Returns `Ok(None)` when there is no
+/// such key and `Err` when the content could not be parsed.
+pub fn set_value(
+    format: Format,
+    content: &str,
+    key: &str,
+    value: &str,
+) -> Result<Option<String>, String> {
+    let path = key_path(key);
+
+    match format {
+        Format::Json => set_json_value(content, &path, value),
+        Format::Yaml => Ok(set_yaml_value(content, &path, value)),
+        Format::Toml => set_toml_value(content, &path, value),
+    }
+}
+
+/// Returns the string value at the key path. Returns `Ok(None)` when there is no
+/// such key or the value is not a string and `Err` when the content could not be parsed.
+pub fn get_value(format: Format, content: &str, key: &str) -> Result<Option<String>, String> {
+    let path = key_path(key);
+
+    match format {
+        Format::Json => {
+            let document: serde_json::Value =
+                serde_json::from_str(content).map_err(|e| e.to_string())?;
+
+            Ok(path
+                .iter()
+                .try_fold(&document, |value, key| value.get(key.as_str()))
+                .and_then(|value| value.as_str())
+                .map(String::from))
+        }
+        Format::Yaml => {
+            Ok(yaml_value_span(content, &path).map(|(start, end)| content[start..end].to_string()))
+        }
+        Format::Toml => {
+            let document = content
+                .parse::<toml_edit::DocumentMut>()
+                .map_err(|e| e.to_string())?;
+
+            Ok(path
+                .iter()
+                .try_fold(document.as_item(), |item, key| item.get(key.as_str()))
+                .and_then(|item| item.as_str())
+                .map(String::from))
+        }
+    }
+}
+
+fn set_json_value(content: &str, path: &[String], value: &str) -> Result<Option<String>, String> {
+    serde_json::from_str::<serde_json::Value>(content).map_err(|e| e.to_string())?;
+
+    Ok(json_value_span(content, path).map(|(start, end)| {
+        format!(
+            "{}{}{}",
+            &content[..start],
+            serde_json::Value::String(value.to_string()),
+            &content[end..]
+        )
+    }))
+}
+
+/// Finds byte range of the value at the key path in a valid JSON document
+fn json_value_span(content: &str, path: &[String]) -> Option<(usize, usize)> {
+    let mut scanner = JsonScanner {
+        bytes: content.as_bytes(),
+        pos: 0,
+    };
+
+    scanner.skip_whitespace();
+    scanner.find(path)
+}
+
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    /// Skips string starting at the current quote, returns its raw content
+    fn skip_string(&mut self) -> Option<&str> {
+        let start = self.pos;
+        self.pos += 1;
+
+        loop {
+            match self.peek()? {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    self.pos += 1;
+                    return std::str::from_utf8(&self.bytes[start..self.pos]).ok();
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => {
+                self.skip_string()?;
+            }
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+
+                loop {
+                    self.skip_whitespace();
+
+                    match self.peek()? {
+                        c if c == close => {
+                            self.pos += 1;
+                            break;
+                        }
+                        b',' | b':' => self.pos += 1,
+                        _ => self.skip_value()?,
+                    }
+                }
+            }
+            // numbers, booleans and null
+            _ => {
+                while !matches!(
+                    self.peek(),
+                    None | Some(b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r')
+                ) {
+                    self.pos += 1;
+                }
+            }
+        }
+
+        Some(())
+    }
+
+    /// Looks for the key path in the value at the current position
+    fn find(&mut self, path: &[String]) -> Option<(usize, usize)> {
+        let Some((key, rest)) = path.split_first() else {
+            let start = self.pos;
+            self.skip_value()?;
+            return Some((start, self.pos));
+        };
+
+        if self.peek()? != b'{' {
+            return None;
+        }
+
+        self.pos += 1;
+
+        loop {
+            self.skip_whitespace();
+
+            match self.peek()? {
+                b'}' => return None,
+                b',' => self.pos += 1,
+                b'"' => {
+                    let name: String = serde_json::from_str(self.skip_string()?).ok()?;
+
+                    self.skip_whitespace();
+                    self.pos += 1; // colon
+                    self.skip_whitespace();
+
+                    if &name == key {
+                        return self.find(rest);
+                    }
+
+                    self.skip_value()?;
+                }
+                _ => return None,
+            }
+        }
+    }
+}
+
+fn set_yaml_value(content: &str, path: &[String], value: &str) -> Option<String> {
+    let (start, end) = yaml_value_span(content, path)?;
+
+    Some(format!("{}{}{}", &content[..start], value, &content[end..]))
+}
+
+/// Finds byte range of the scalar value at the key path in block mappings.
+/// For quoted values the range does not include quotes, so the quoting
+/// style is kept. Items of sequences are addressed by index, e.g. `dependencies.0.version`.
+fn yaml_value_span(content: &str, path: &[String]) -> Option<(usize, usize)> {
+    yaml_scalars(content)
+        .into_iter()
+        .find(|(keys, _)| keys == path)
+        .map(|(_, span)| span)
+}
+
+/// Node of the path to the current line of a YAML document
+struct YamlNode {
+    indent: usize,
+    key: String,
+    /// Sequence item, its key is the index
+    item: bool,
+    /// Mapping key without a value on the same line, may be followed by a sequence on the same indent
+    open: bool,
+}
+
+/// Returns key paths and byte ranges of all scalar values of block mappings
+fn yaml_scalars(content: &str) -> Vec<(Vec<String>, (usize, usize))> {
+    let line_rx = Regex::new(
+        r#"^(?P<indent> *)(?P<item>- +)?(?P<key>"[^"]*"|'[^']*'|[^\s#'"\-][^:#]*?) *:(?: +|$)(?P<rest>.*)$"#,
+    )
+    .unwrap();
+
+    let mut stack: Vec<YamlNode> = Vec::new();
+    let mut scalars = Vec::new();
+    let mut block_scalar_indent: Option<usize> = None;
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let line_start = offset;
+        offset += line.len();
+
+        let text = line.trim_end_matches(['\n', '\r']);
+        let indent = text.len() - text.trim_start().len();
+
+        if text.trim().is_empty() || text.trim_start().starts_with('#') {
+            continue;
+        }
+
+        if let Some(block_indent) = block_scalar_indent {
+            if indent > block_indent {
+                continue;
+            }
+
+            block_scalar_indent = None;
+        }
+
+        let trimmed = text.trim_start();
+        let mut key_indent = indent;
+
+        if trimmed == "-" || trimmed.starts_with("- ") {
+            let index = stack
+                .iter()
+                .find(|node| node.item && node.indent == indent)
+                .and_then(|node| node.key.parse::<usize>().ok())
+                .map_or(0, |index| index + 1);
+
+            // Sequence may have the same indent as the key containing it
+            stack.retain(|node| {
+                node.indent < indent || (node.indent == indent && node.open && !node.item)
+            });
+            stack.push(YamlNode {
+                indent,
+                key: index.to_string(),
+                item: true,
+                open: false,
+            });
+
+            key_indent += trimmed.len() - trimmed[1..].trim_start().len();
+        } else {
+            stack.retain(|node| node.indent < indent);
+        }
+
+        let Some(caps) = line_rx.captures(text) else {
+            continue;
+        };
+
+        let rest = caps.name("rest").unwrap();
+
+        stack.push(YamlNode {
+            indent: key_indent,
+            key: caps["key"].trim_matches(['"', '\'']).to_string(),
+            item: false,
+            open: rest.as_str().is_empty(),
+        });
+
+        if rest.as_str().starts_with(['|', '>']) {
+            block_scalar_indent = Some(key_indent);
+        }
+
+        if rest.as_str().is_empty() {
+            continue;
+        }
+
+        let rest_start = line_start + rest.start();
+        let rest = rest.as_str();
+
+        let span = match rest.chars().next() {
+            Some(quote @ ('"' | '\'')) => match rest[1..].find(quote) {
+                Some(end) => (rest_start + 1, rest_start + 1 + end),
+                None => continue,
+            },
+            _ => {
+                let end = rest.find(" #").unwrap_or(rest.len());
+                (rest_start, rest_start + rest[..end].trim_end().len())
+            }
+        };
+
+        let keys = stack.iter().map(|node| node.key.clone()).collect();
+        scalars.push((keys, span));
+    }
+
+    scalars
+}
+
+/// Returns key paths of versions and directories of local (`file://`) dependencies of a Helm chart
+pub fn helm_local_dependencies(content: &str) -> Vec<(String, String)> {
+    yaml_scalars(content)
+        .into_iter()
+        .filter_map(|(keys, (start, end))| match keys.as_slice() {
+            [dependencies, index, repository]
+                if dependencies == "dependencies" && repository == "repository" =>
+            {
+                let path = content[start..end].strip_prefix("file://")?;
+
+                Some((format!("dependencies.{}.version", index), path.to_string()))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+fn set_toml_value(content: &str, path: &[String], value: &str) -> Result<Option<String>, String> {
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    let Some(item) = path.iter().try_fold(document.as_item_mut(), |item, key| {
+        item.get_mut(key.as_str())
+    }) else {
+        return Ok(None);
+    };
+
+    if !set_toml_item(item, value) {
+        return Ok(None);
+    }
+
+    Ok(Some(document.to_string()))
+}
+
+/// Replaces the TOML value keeping comments and whitespace around it
+fn set_toml_item(item: &mut toml_edit::Item, value: &str) -> bool {
+    let Some(old_value) = item.as_value() else {
+        return false;
+    };
+
+    let decor = old_value.decor().clone();
+    let mut new_value = toml_edit::Value::from(value);
+    *new_value.decor_mut() = decor;
+    *item = toml_edit::Item::Value(new_value);
+
+    true
+}
+
+/// Cargo manifest with the bumped version
+#[derive(Debug, PartialEq)]
+pub struct CargoManifest {
+    pub content: String,
+    /// Names and previous versions of the bumped packages, used to update Cargo.lock
+    pub bumped: Vec<(String, String)>,
+    /// Previous `[workspace.package].version`
+    pub workspace_version: Option<String>,
+    /// Patterns of the workspace members
+    pub members: Vec<String>,
+    /// Paths excluded from the workspace members
+    pub exclude: Vec<String>,
+}
+
+/// Sets `[package].version` and `[workspace.package].version` of the Cargo manifest.
+/// Workspace member is given the previous version of the workspace as `workspace_version`:
+/// the member is bumped when it inherits the version or has the same one,
+/// members with other versions are released on their own.
+pub fn set_cargo_version(
+    content: &str,
+    version: &str,
+    workspace_version: Option<&str>,
+) -> Result<CargoManifest, String> {
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    let mut manifest = CargoManifest {
+        content: String::new(),
+        bumped: Vec::new(),
+        workspace_version: None,
+        members: Vec::new(),
+        exclude: Vec::new(),
+    };
+
+    if let Some(workspace) = document.get_mut("workspace") {
+        // `Item::get_mut` would insert an empty `package` into a virtual workspace
+        if let Some(item) = workspace
+            .as_table_like_mut()
+            .and_then(|workspace| workspace.get_mut("package"))
+            .and_then(|package| package.as_table_like_mut())
+            .and_then(|package| package.get_mut("version"))
+        {
+            manifest.workspace_version = item.as_str().map(String::from);
+            set_toml_item(item, version);
+        }
+
+        let patterns = |key: &str| -> Vec<String> {
+            workspace
+                .get(key)
+                .and_then(|patterns| patterns.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|pattern| pattern.as_str().map(String::from))
+                .collect()
+        };
+
+        manifest.members = patterns("members");
+        manifest.exclude = patterns("exclude");
+    }
+
+    let is_member = workspace_version.is_some();
+    let workspace_version = workspace_version.or(manifest.workspace_version.as_deref());
+
+    if let Some(package) = document.get_mut("package") {
+        let name = package
+            .get("name")
+            .and_then(|name| name.as_str())
+            .map(String::from);
+
+        let inherited = package
+            .get("version")
+            .and_then(|version| version.get("workspace"))
+            .and_then(|workspace| workspace.as_bool())
+            .unwrap_or(false);
+
+        match (name, package.get_mut("version")) {
+            (Some(name), Some(item))
+                if item.is_str() && (!is_member || item.as_str() == workspace_version) =>
+            {
+                manifest
+                    .bumped
+                    .push((name, item.as_str().unwrap_or_default().to_string()));
+                set_toml_item(item, version);
+            }
+            (Some(name), Some(_)) if inherited => {
+                if let Some(workspace_version) = workspace_version {
+                    manifest.bumped.push((name, workspace_version.to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    manifest.content = document.to_string();
+
+    Ok(manifest)
+}
+
+/// Sets version requirement of local (with `path`) dependencies on the bumped packages,
+/// otherwise a breaking bump of a workspace member would break the build
+pub fn set_cargo_dependency_versions(
+    content: &str,
+    names: &[String],
+    version: &str,
+) -> Result<String, String> {
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    let mut tables: Vec<&mut toml_edit::Item> = Vec::new();
+
+    for (key, item) in document.iter_mut() {
+        match key.get() {
+            "dependencies" | "dev-dependencies" | "build-dependencies" => tables.push(item),
+            "workspace" => {
+                if let Some(dependencies) = item.get_mut("dependencies") {
+                    tables.push(dependencies);
+                }
+            }
+            "target" => {
+                if let Some(targets) = item.as_table_like_mut() {
+                    for (_, target) in targets.iter_mut() {
+                        for (key, item) in target
+                            .as_table_like_mut()
+                            .into_iter()
+                            .flat_map(|t| t.iter_mut())
+                        {
+                            if key.get().ends_with("dependencies") {
+                                tables.push(item);
+                            }
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for table in tables {
+        let Some(table) = table.as_table_like_mut() else {
+            continue;
+        };
+
+        for (key, dependency) in table.iter_mut() {
+            let Some(dependency) = dependency.as_table_like_mut() else {
+                continue;
+            };
+
+            // Dependency may be renamed with the `package` key
+            let name = dependency
+                .get("package")
+                .and_then(|package| package.as_str())
+                .unwrap_or(key.get());
+
+            if dependency.get("path").is_none() || !names.iter().any(|n| n == name) {
+                continue;
+            }
+
+            if let Some(item) = dependency.get_mut("version") {
+                // Keep the requirement operator, e.g. `=` in `=0.1.0`
+                let requirement = item.as_str().unwrap_or_default();
+                let bare = requirement.trim_start_matches(|c: char| !c.is_ascii_digit());
+                let operator = requirement[..requirement.len() - bare.len()].trim();
+
+                // Wildcards and ranges, e.g. `*` or `>=1, <2`, are left as they are
+                if !["", "^", "~", "="].contains(&operator)
+                    || bare.is_empty()
+                    || bare.contains([',', '*'])
+                {
+                    continue;
+                }
+
+                set_toml_item(item, &format!("{}{}", operator, version));
+            }
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Sets version of the bumped packages in Cargo.lock
+pub fn set_cargo_lock_version(
+    content: &str,
+    bumped: &[(String, String)],
+    version: &str,
+) -> Result<String, String> {
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    if let Some(packages) = document
+        .get_mut("package")
+        .and_then(|packages| packages.as_array_of_tables_mut())
+    {
+        for package in packages.iter_mut() {
+            let name = package.get("name").and_then(|name| name.as_str());
+            let old = package.get("version").and_then(|version| version.as_str());
+
+            let is_bumped = bumped
+                .iter()
+                .any(|(n, v)| Some(n.as_str()) == name && Some(v.as_str()) == old);
+
+            if is_bumped {
+                if let Some(item) = package.get_mut("version") {
+                    set_toml_item(item, version);
+                }
+            }
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+/// Sets the root `version` and `packages[""].version` of npm lockfile. The latter is absent
+/// in `lockfileVersion` 1, the former is absent in `lockfileVersion` 3 of some npm versions.
+pub fn set_npm_lock_version(content: &str, version: &str) -> Result<Option<String>, String> {
+    let mut replaced: Option<String> = None;
+
+    for path in [vec!["version"], vec!["packages", "", "version"]] {
+        let path: Vec<String> = path.into_iter().map(String::from).collect();
+        let current = replaced.as_deref().unwrap_or(content);
+
+        if let Some(content) = set_json_value(current, &path, version)? {
+            replaced = Some(content);
+        }
+    }
+
+    Ok(replaced)
+}
+
+/// Sets version of a Python project, the kind of file is detected by its name:
+/// `pyproject.toml` (`[project]` or `[tool.poetry]`), `setup.cfg` (`[metadata]`)
+/// or a module with `__version__`. Returns `Ok(None)` when there is no static version.
+pub fn set_python_version(
+    path: &str,
+    content: &str,
+    version: &str,
+) -> Result<Option<String>, String> {
+    let name = Path::new(path)
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+
+    match name {
+        "pyproject.toml" => set_pyproject_version(content, version),
+        "setup.cfg" => Ok(set_setup_cfg_version(content, version)),
+        _ if name.ends_with(".py") => Ok(set_module_version(content, version)),
+        _ => Err(String::from("unknown Python version file")),
+    }
+}
+
+fn set_pyproject_version(content: &str, version: &str) -> Result<Option<String>, String> {
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|e| e.to_string())?;
+
+    // PEP 621 metadata takes precedence over Poetry's own section
+    let in_project = document
+        .get("project")
+        .and_then(|project| project.get("version"))
+        .is_some_and(|version| version.is_str());
+
+    let item = if in_project {
+        document
+            .get_mut("project")
+            .and_then(|project| project.get_mut("version"))
+    } else {
+        document
+            .get_mut("tool")
+            .and_then(|tool| tool.get_mut("poetry"))
+            .and_then(|poetry| poetry.get_mut("version"))
+            .filter(|version| version.is_str())
+    };
+
+    let Some(item) = item else {
+        return Ok(None);
+    };
+
+    set_toml_item(item, version);
+
+    Ok(Some(document.to_string()))
+}
+
+/// Replaces `version = ...` in the `[metadata]` section, versions
+/// read by setuptools from elsewhere (`attr:` or `file:`) are left as is
+fn set_setup_cfg_version(content: &str, version: &str) -> Option<String> {
+    let rx = Regex::new(r"^version\s*[=:][ \t]*(?P<version>\S.*?)\s*$").unwrap();
+    let mut section = "";
+    let mut offset = 0;
+
+    for line in content.split_inclusive('\n') {
+        let trimmed = line.trim();
+
+        if trimmed.starts_with('[') && trimmed.ends_with(']') {
+            section = trimmed;
+        } else if section == "[metadata]" {
+            if let Some(caps) = rx.captures(line.trim_end_matches(['\r', '\n'])) {
+                let old = &caps["version"];
+
+                if old.starts_with("attr:") || old.starts_with("file:") {
+                    return None;
+                }
+
+                let start = offset + caps.name("version")?.start();
+                let end = offset + caps.name("version")?.end();
+
+                return Some(format!(
+                    "{}{}{}",
+                    &content[..start],
+                    version,
+                    &content[end..]
+                ));
+            }
+        }
+
+        offset += line.len();
+    }
+
+    None
+}
+
+/// Replaces the string assigned to `__version__` keeping its quotes
+fn set_module_version(content: &str, version: &str) -> Option<String> {
+    let rx = Regex::new(
+        r#"(?m)^__version__\s*(?::\s*str\s*)?=\s*(?:"(?P<double>[^"\n]*)"|'(?P<single>[^'\n]*)')"#,
+    )
+    .unwrap();
+
+    let caps = rx.captures(content)?;
+    let old = caps.name("double").or_else(|| caps.name("single"))?;
+
+    Some(format!(
+        "{}{}{}",
+        &content[..old.start()],
+        version,
+        &content[old.end()..]
+    ))
+}
+
+/// Replaces placeholders of the replacement template from the config
+pub fn render_template(template: &str, version: &Version, tag: &str) -> String {
+    template
+        .replace("{version}", &version.to_string())
+        .replace("{major}", &version.major.to_string())
+        .replace("{minor}", &version.minor.to_string())
+        .replace("{patch}", &version.patch.to_string())
+        .replace("{tag}", tag)
+}
+
+/// Replaces every match of the user-defined regex with the rendered template,
+/// or only its `version` group with the version when there is no template
+pub fn set_regex_version(
+    content: &str,
+    regex: &Regex,
+    replacement: Option<&str>,
+    version: &Version,
+    tag: &str,
+) -> Option<String> {
+    if !regex.is_match(content) {
+        return None;
+    }
+
+    let replaced = regex.replace_all(content, |caps: &Captures| {
+        let whole = caps.get(0).unwrap();
+
+        match (replacement, caps.name("version")) {
+            (Some(template), _) => render_template(template, version, tag),
+            (None, Some(group)) => format!(
+                "{}{}{}",
+                &content[whole.start()..group.start()],
+                version,
+                &content[group.end()..whole.end()]
+            ),
+            (None, None) => whole.as_str().to_string(),
+        }
+    });
+
+    Some(replaced.to_string())
+}
+
+#[test]
+fn test_json() {
+    use indoc::indoc;
+
+    let content = indoc! {r#"
+        {
+          "name": "app",
+          "dependencies": {
+            "lib": { "version": "1.0.0" }
+          },
+          "version": "0.1.0",
+          "scripts": {"build": "tsc"}
+        }
+    "#};
+
+    assert_eq!(
+        set_value(Format::Json, content, "$.version", "v0.2.0").unwrap(),
+        Some(content.replace(r#""version": "0.1.0""#, r#""version": "v0.2.0""#))
+    );
+
+    assert_eq!(
+        set_value(Format::Json, content, "dependencies.lib.version", "2.0.0").unwrap(),
+        Some(content.replace(r#"{ "version": "1.0.0" }"#, r#"{ "version": "2.0.0" }"#))
+    );
+
+    assert_eq!(
+        set_value(Format::Json, content, "$.missing", "1").unwrap(),
+        None
+    );
+    assert!(set_value(Format::Json, "{ broken", "version", "1").is_err());
+    assert_eq!(
+        get_value(Format::Json, content, "dependencies.lib.version"),
+        Ok(Some(String::from("1.0.0")))
+    );
+}
+
+#[test]
+fn test_yaml() {
+    use indoc::indoc;
+
+    let content = indoc! {r#"
+        apiVersion: v2
+        description: |
+          version: not a key
+        version: 0.1.0 # chart version
+        appVersion: "1.0.0"
+        dependencies:
+          - name: lib
+            version: 1.0.0
+        image:
+          version: 1.0.0
+    "#};
+
+    assert_eq!(
+        set_value(Format::Yaml, content, "appVersion", "v1.1.0").unwrap(),
+        Some(content.replace(r#"appVersion: "1.0.0""#, r#"appVersion: "v1.1.0""#))
+    );
+
+    assert_eq!(
+        set_value(Format::Yaml, content, "version", "0.2.0").unwrap(),
+        Some(content.replace("version: 0.1.0 #", "version: 0.2.0 #"))
+    );
+
+    assert_eq!(
+        set_value(Format::Yaml, content, "image.version", "2.0.0").unwrap(),
+        Some(content.replace("image:\n  version: 1.0.0", "image:\n  version: 2.0.0"))
+    );
+
+    assert_eq!(
+        set_value(Format::Yaml, content, "dependencies.0.version", "1.1.0").unwrap(),
+        Some(content.replace("lib\n    version: 1.0.0", "lib\n    version: 1.1.0"))
+    );
+
+    assert_eq!(set_value(Format::Yaml, content, "name", "x").unwrap(), None);
+    assert_eq!(
+        get_value(Format::Yaml, content, "appVersion"),
+        Ok(Some(String::from("1.0.0")))
+    );
+
+    let sequence = indoc! {"
+        dependencies:
+        - name: a
+          version: 1.0.0
+        - name: b
+          version: 1.0.0
+        version: 1.0.0
+    "};
+
+    assert_eq!(
+        set_value(Format::Yaml, sequence, "dependencies.1.version", "2.0.0").unwrap(),
+        Some(sequence.replace("b\n  version: 1.0.0", "b\n  version: 2.0.0"))
+    );
+    assert_eq!(
+        set_value(Format::Yaml, sequence, "version", "2.0.0").unwrap(),
+        Some(sequence.replace("\nversion: 1.0.0", "\nversion: 2.0.0"))
+    );
+
+    let chart = indoc! {r#"
+        dependencies:
+          - name: common
+            version: 1.0.0
+            repository: "file://../common"
+          - name: redis
+            version: 17.0.0
+            repository: https://charts.bitnami.com/bitnami
+    "#};
+
+    assert_eq!(
+        helm_local_dependencies(chart),
+        vec![(
+            String::from("dependencies.0.version"),
+            String::from("../common")
+        )]
+    );
+}
+
+#[test]
+fn test_toml() {
+    use indoc::indoc;
+
+    let content = indoc! {r#"
+        [package]
+        name = "app"   # name
+        version = "0.1.0"   # version
+
+        [dependencies]
+        lib = { version = "1.0.0" }
+    "#};
+
+    assert_eq!(
+        set_value(Format::Toml, content, "package.version", "0.2.0").unwrap(),
+        Some(content.replace(r#"version = "0.1.0""#, r#"version = "0.2.0""#))
+    );
+
+    assert_eq!(
+        set_value(Format::Toml, content, "dependencies.lib.version", "2.0.0").unwrap(),
+        Some(content.replace(r#"{ version = "1.0.0" }"#, r#"{ version = "2.0.0" }"#))
+    );
+    assert_eq!(
+        set_value(Format::Toml, content, "version", "1").unwrap(),
+        None
+    );
+    assert_eq!(
+        get_value(Format::Toml, content, "package.version"),
+        Ok(Some(String::from("0.1.0")))
+    );
+}
+
+#[test]
+fn test_cargo() {
+    use indoc::indoc;
+
+    let workspace = indoc! {r#"
+        [workspace]
+        members = ["crates/*"]
+
+        [workspace.package]
+        version = "0.1.0" # shared
+
+        [package]
+        name = "app"
+        version = "0.1.0"
+    "#};
+
+    let manifest = set_cargo_version(workspace, "0.2.0", None).unwrap();
+
+    assert_eq!(
+        manifest.content,
+        workspace.replace(r#"version = "0.1.0""#, r#"version = "0.2.0""#)
+    );
+    assert_eq!(manifest.workspace_version, Some("0.1.0".to_string()));
+    assert_eq!(manifest.members, vec!["crates/*"]);
+    assert_eq!(
+        manifest.bumped,
+        vec![("app".to_string(), "0.1.0".to_string())]
+    );
+
+    let virtual_workspace = "[workspace]\nmembers = [\"crates/*\"]\n";
+    let manifest = set_cargo_version(virtual_workspace, "0.2.0", None).unwrap();
+
+    assert_eq!(manifest.content, virtual_workspace);
+    assert_eq!(manifest.workspace_version, None);
+    assert_eq!(manifest.members, vec!["crates/*"]);
+
+    let member = indoc! {r#"
+        [package]
+        name = "lib"
+        version.workspace = true
+    "#};
+
+    let manifest = set_cargo_version(member, "0.2.0", Some("0.1.0")).unwrap();
+
+    assert_eq!(manifest.content, member);
+    assert_eq!(
+        manifest.bumped,
+        vec![("lib".to_string(), "0.1.0".to_string())]
+    );
+
+    let same_version = "[package]\nname = \"cli\"\nversion = \"0.1.0\"\n";
+    let manifest = set_cargo_version(same_version, "0.2.0", Some("0.1.0")).unwrap();
+
+    assert_eq!(manifest.content, same_version.replace("0.1.0", "0.2.0"));
+
+    // Member with its own version is released separately
+    let own_version = "[package]\nname = \"macros\"\nversion = \"3.0.0\"\n";
+    let manifest = set_cargo_version(own_version, "0.2.0", Some("0.1.0")).unwrap();
+
+    assert_eq!(manifest.content, own_version);
+    assert!(manifest.bumped.is_empty());
+
+    let excluding = "[workspace]\nmembers = [\"crates/*\"]\nexclude = [\"crates/legacy\"]\n";
+
+    assert_eq!(
+        set_cargo_version(excluding, "0.2.0", None).unwrap().exclude,
+        vec!["crates/legacy"]
+    );
+
+    let lock = indoc! {r#"
+        version = 3
+
+        [[package]]
+        name = "app"
+        version = "0.1.0"
+
+        [[package]]
+        name = "lib"
+        version = "0.1.0"
+
+        [[package]]
+        name = "serde"
+        version = "0.1.0"
+    "#};
+
+    let bumped = vec![
+        ("app".to_string(), "0.1.0".to_string()),
+        ("lib".to_string(), "0.1.0".to_string()),
+    ];
+
+    assert_eq!(
+        set_cargo_lock_version(lock, &bumped, "0.2.0").unwrap(),
+        lock.replacen(r#"version = "0.1.0""#, r#"version = "0.2.0""#, 2)
+    );
+
+    let dependent = indoc! {r#"
+        [dependencies]
+        lib = { path = "../lib", version = "=0.1.0" }
+        serde = { version = "0.1.0" }
+
+        [dev-dependencies.app]
+        path = "../app"
+        version = "0.1.0"
+
+        [build-dependencies]
+        lib = { path = "../lib", version = "*" }
+        app = { path = "../app", version = ">=0.1, <1" }
+    "#};
+
+    assert_eq!(
+        set_cargo_dependency_versions(dependent, &["lib".to_string(), "app".to_string()], "0.2.0")
+            .unwrap(),
+        indoc! {r#"
+            [dependencies]
+            lib = { path = "../lib", version = "=0.2.0" }
+            serde = { version = "0.1.0" }
+
+            [dev-dependencies.app]
+            path = "../app"
+            version = "0.2.0"
+
+            [build-dependencies]
+            lib = { path = "../lib", version = "*" }
+            app = { path = "../app", version = ">=0.1, <1" }
+        "#}
+    );
+}
+
+#[test]
+fn test_python() {
+    use indoc::indoc;
+
+    let pyproject = indoc! {r#"
+        [project]
+        name = "app"
+        version = "1.2.3"  # released by CI
+
+        [tool.poetry]
+        version = "0.0.0"
+    "#};
+
+    assert_eq!(
+        set_python_version("pyproject.toml", pyproject, "1.3.0").unwrap(),
+        Some(pyproject.replace("1.2.3", "1.3.0"))
+    );
+
+    let poetry = indoc! {r#"
+        [tool.poetry]
+        name = "app"
+        version = "1.2.3"
+    "#};
+
+    assert_eq!(
+        set_python_version("api/pyproject.toml", poetry, "1.3.0").unwrap(),
+        Some(poetry.replace("1.2.3", "1.3.0"))
+    );
+    assert_eq!(
+        set_python_version(
+            "pyproject.toml",
+            "[project]\ndynamic = [\"version\"]\n",
+            "1.3.0"
+        ),
+        Ok(None)
+    );
+
+    let setup_cfg = indoc! {"
+        [options]
+        version = 0.0.0
+
+        [metadata]
+        name = app
+        version = 1.2.3
+    "};
+
+    assert_eq!(
+        set_python_version("setup.cfg", setup_cfg, "1.3.0").unwrap(),
+        Some(setup_cfg.replace("1.2.3", "1.3.0"))
+    );
+    assert_eq!(
+        set_python_version(
+            "setup.cfg",
+            "[metadata]\nversion = attr: app.__version__\n",
+            "1.3.0"
+        ),
+        Ok(None)
+    );
+
+    let module = indoc! {r#"
+        """App"""
+
+        __version__ = '1.2.3'
+        version_info = (1, 2, 3)
+    "#};
+
+    assert_eq!(
+        set_python_version("app/__init__.py", module, "1.3.0").unwrap(),
+        Some(module.replace("1.2.3", "1.3.0"))
+    );
+    assert!(set_python_version("setup.py", "", "1.3.0")
+        .unwrap()
+        .is_none());
+    assert!(set_python_version("VERSION", "", "1.3.0").is_err());
+}
+
+#[test]
+fn test_regex() {
+    let version: Version = "1.3.0-rc.1".parse().unwrap();
+    let dockerfile = "FROM alpine\nARG VERSION=1.2.3\n";
+    let regex = Regex::new(r"ARG VERSION=(?P<version>\S+)").unwrap();
+
+    assert_eq!(
+        set_regex_version(dockerfile, &regex, None, &version, "v1.3.0-rc.1"),
+        Some(String::from("FROM alpine\nARG VERSION=1.3.0-rc.1\n"))
+    );
+
+    let readme = "![v1.2.3](badge/v1.2.3) and ![v1.2.3](badge/v1.2.3)";
+    let regex = Regex::new(r"badge/v(?P<version>[0-9.]+)").unwrap();
+
+    assert_eq!(
+        set_regex_version(
+            readme,
+            &regex,
+            Some("badge/{major}.{minor}"),
+            &version,
+            "v1.3.0"
+        ),
+        Some(String::from(
+            "![v1.2.3](badge/1.3) and ![v1.2.3](badge/1.3)"
+        ))
+    );
+    assert_eq!(
+        set_regex_version("", &regex, None, &version, "v1.3.0"),
+        None
+    );
+    assert_eq!(
+        render_template("{tag} {version} {patch}", &version, "v1.3.0-rc.1"),
+        "v1.3.0-rc.1 1.3.0-rc.1 0"
+    );
+}
+
+#[test]
+fn test_npm_lock() {
+    use indoc::indoc;
+
+    let lock = indoc! {r#"
+        {
+          "name": "app",
+          "version": "1.0.0",
+          "lockfileVersion": 3,
+          "packages": {
+            "": {
+              "name": "app",
+              "version": "1.0.0"
+            },
+            "node_modules/lib": {
+              "version": "1.0.0"
+            }
+          }
+        }
+    "#};
+
+    assert_eq!(
+        set_npm_lock_version(lock, "1.1.0").unwrap(),
+        Some(lock.replacen(r#""version": "1.0.0""#, r#""version": "1.1.0""#, 2))
+    );
+    assert_eq!(set_npm_lock_version("{}", "1.1.0").unwrap(), None);
+    assert!(set_npm_lock_version("{", "1.1.0").is_err());
+}
@@ -0,0 +1,205 @@
+/*!
+ * Semantic version (SemVer 2.0) with pre-release and build metadata
+ */
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use regex::{Captures, Regex};
+
+pub const SEMVER_RX: &str = r"(?P<major>0|[1-9]\d*)\.(?P<minor>0|[1-9]\d*)\.(?P<patch>0|[1-9]\d*)(?:-(?P<prerelease>(?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*)(?:\.(?:0|[1-9]\d*|\d*[a-zA-Z-][0-9a-zA-Z-]*))*))?(?:\+(?P<build>[0-9a-zA-Z-]+(?:\.[0-9a-zA-Z-]+)*))?";
+
+/// Dot-separated part of the pre-release version, e.g. `rc` or `1` in `1.0.0-rc.1`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Identifier {
+    fn parse(identifier: &str) -> Identifier {
+        match identifier.parse::<u64>() {
+            Ok(number) if !identifier.starts_with('0') || identifier == "0" => {
+                Identifier::Numeric(number)
+            }
+            _ => Identifier::AlphaNumeric(identifier.to_string()),
+        }
+    }
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+            // Numeric identifiers always have lower precedence than alphanumeric ones
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(number) => write!(f, "{}", number),
+            Identifier::AlphaNumeric(string) => write!(f, "{}", string),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: Vec<String>,
+}
+
+impl Version {
+    pub fn new(major: u64, minor: u64, patch: u64) -> Version {
+        Version {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: Vec::new(),
+        }
+    }
+
+    /// Builds a version from the captures of [`SEMVER_RX`]
+    pub fn from_captures(caps: &Captures) -> Option<Version> {
+        Some(Version {
+            major: caps["major"].parse().ok()?,
+            minor: caps["minor"].parse().ok()?,
+            patch: caps["patch"].parse().ok()?,
+            pre: caps
+                .name("prerelease")
+                .map(|pre| pre.as_str().split('.').map(Identifier::parse).collect())
+                .unwrap_or_default(),
+            build: caps
+                .name("build")
+                .map(|build| build.as_str().split('.').map(String::from).collect())
+                .unwrap_or_default(),
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Compares versions by SemVer precedence, which ignores build metadata
+    pub fn precedence(&self, other: &Version) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                // A pre-release version has lower precedence than a normal version
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    /// Parses a string which must be exactly a version, without any prefix
+    fn from_str(version: &str) -> Result<Version, Self::Err> {
+        let re = Regex::new(format!("^{}$", SEMVER_RX).as_str()).unwrap();
+
+        re.captures(version)
+            .and_then(|caps| Version::from_captures(&caps))
+            .ok_or_else(|| format!("`{}` is not a valid semantic version", version))
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Build metadata does not affect precedence, it is compared only
+        // to keep ordering consistent with equality.
+        self.precedence(other)
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+
+        if !self.pre.is_empty() {
+            let pre: Vec<String> = self.pre.iter().map(|i| i.to_string()).collect();
+            write!(f, "-{}", pre.join("."))?;
+        }
+
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build.join("."))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_parse() {
+    let version = "1.2.0-rc.1+build.5".parse::<Version>().unwrap();
+
+    assert_eq!((version.major, version.minor, version.patch), (1, 2, 0));
+    assert_eq!(
+        version.pre,
+        vec![
+            Identifier::AlphaNumeric("rc".to_string()),
+            Identifier::Numeric(1)
+        ]
+    );
+    assert_eq!(version.build, vec!["build", "5"]);
+    assert_eq!(version.to_string(), "1.2.0-rc.1+build.5");
+
+    assert!("v1.2.0".parse::<Version>().is_err());
+    assert!("1.2".parse::<Version>().is_err());
+    assert!("01.2.0".parse::<Version>().is_err());
+    assert!("1.2.0-01".parse::<Version>().is_err());
+}
+
+#[test]
+fn test_precedence() {
+    let ordered = [
+        "1.0.0-alpha",
+        "1.0.0-alpha.1",
+        "1.0.0-alpha.beta",
+        "1.0.0-beta",
+        "1.0.0-beta.2",
+        "1.0.0-beta.11",
+        "1.0.0-rc.1",
+        "1.0.0",
+        "1.0.1",
+        "1.1.0",
+        "2.0.0",
+    ];
+
+    for pair in ordered.windows(2) {
+        let a: Version = pair[0].parse().unwrap();
+        let b: Version = pair[1].parse().unwrap();
+
+        assert!(a < b, "{} < {}", a, b);
+    }
+
+    let a: Version = "1.0.0+build.1".parse().unwrap();
+    let b: Version = "1.0.0+build.2".parse().unwrap();
+    assert_eq!(a.precedence(&b), Ordering::Equal);
+}